@@ -0,0 +1,486 @@
+use crate::error::Error;
+use crate::polynomial::GaloisField;
+use crate::qr_code::Matrix;
+use crate::render;
+use crate::render::Symbol;
+use std::fmt;
+
+// word size, primitive polynomial, field size
+pub const AZTEC_GALOIS_FIELDS: [(usize, u32, u32); 5] = [
+    (4, 0x13, 16),
+    (6, 0x43, 64),
+    (8, 0x12d, 256),
+    (10, 0x409, 1024),
+    (12, 0x1069, 4096),
+];
+// codeword size in bits by number of layers, index 0 is the mode message
+pub const AZTEC_WORD_SIZE: [usize; 33] = [
+    4, 6, 6, 8, 8, 8, 8, 8, 8, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 12, 12, 12,
+    12, 12, 12, 12, 12, 12, 12,
+];
+pub const AZTEC_MAX_BINARY_SHIFT: usize = 2047 + 31;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AztecMode {
+    Upper,
+    Lower,
+    Mixed,
+    Punct,
+    Digit,
+}
+pub const AZTEC_MODES: [AztecMode; 5] = [
+    AztecMode::Upper,
+    AztecMode::Lower,
+    AztecMode::Mixed,
+    AztecMode::Punct,
+    AztecMode::Digit,
+];
+
+fn mode_code(mode: AztecMode, byte: u8) -> Option<u32> {
+    let code = match (mode, byte) {
+        (AztecMode::Upper, b' ') | (AztecMode::Lower, b' ') | (AztecMode::Digit, b' ') => 1,
+        (AztecMode::Upper, b'A'..=b'Z') => byte - b'A' + 2,
+        (AztecMode::Lower, b'a'..=b'z') => byte - b'a' + 2,
+        (AztecMode::Digit, b'0'..=b'9') => byte - b'0' + 2,
+        (AztecMode::Digit, b',') => 12,
+        (AztecMode::Digit, b'.') => 13,
+        (AztecMode::Mixed, b' ') => 1,
+        (AztecMode::Mixed, 1..=13) => byte + 1,
+        (AztecMode::Mixed, 27..=31) => byte - 12,
+        (AztecMode::Mixed, b'@') => 20,
+        (AztecMode::Mixed, b'\\') => 21,
+        (AztecMode::Mixed, b'^') => 22,
+        (AztecMode::Mixed, b'_') => 23,
+        (AztecMode::Mixed, b'`') => 24,
+        (AztecMode::Mixed, b'|') => 25,
+        (AztecMode::Mixed, b'~') => 26,
+        (AztecMode::Mixed, 127) => 27,
+        (AztecMode::Punct, b'\r') => 1,
+        (AztecMode::Punct, b'!'..=b'/') => byte - b'!' + 6,
+        (AztecMode::Punct, b':'..=b'?') => byte - b':' + 21,
+        (AztecMode::Punct, b'[') => 27,
+        (AztecMode::Punct, b']') => 28,
+        (AztecMode::Punct, b'{') => 29,
+        (AztecMode::Punct, b'}') => 30,
+        _ => return None,
+    };
+    Some(code as u32)
+}
+
+fn pair_code(first: u8, second: u8) -> Option<u32> {
+    match (first, second) {
+        (b'\r', b'\n') => Some(2),
+        (b'.', b' ') => Some(3),
+        (b',', b' ') => Some(4),
+        (b':', b' ') => Some(5),
+        _ => None,
+    }
+}
+
+fn code_width(mode: AztecMode) -> u32 {
+    if mode == AztecMode::Digit {
+        4
+    } else {
+        5
+    }
+}
+
+// (value, bits) codes to latch from one mode to another
+fn latch(from: AztecMode, to: AztecMode) -> &'static [(u32, u32)] {
+    match (from, to) {
+        (AztecMode::Upper, AztecMode::Lower) => &[(28, 5)],
+        (AztecMode::Upper, AztecMode::Mixed) => &[(29, 5)],
+        (AztecMode::Upper, AztecMode::Punct) => &[(29, 5), (30, 5)],
+        (AztecMode::Upper, AztecMode::Digit) => &[(30, 5)],
+        (AztecMode::Lower, AztecMode::Upper) => &[(30, 5), (14, 4)],
+        (AztecMode::Lower, AztecMode::Mixed) => &[(29, 5)],
+        (AztecMode::Lower, AztecMode::Punct) => &[(29, 5), (30, 5)],
+        (AztecMode::Lower, AztecMode::Digit) => &[(30, 5)],
+        (AztecMode::Mixed, AztecMode::Upper) => &[(29, 5)],
+        (AztecMode::Mixed, AztecMode::Lower) => &[(28, 5)],
+        (AztecMode::Mixed, AztecMode::Punct) => &[(30, 5)],
+        (AztecMode::Mixed, AztecMode::Digit) => &[(29, 5), (30, 5)],
+        (AztecMode::Punct, AztecMode::Upper) => &[(31, 5)],
+        (AztecMode::Punct, AztecMode::Lower) => &[(31, 5), (28, 5)],
+        (AztecMode::Punct, AztecMode::Mixed) => &[(31, 5), (29, 5)],
+        (AztecMode::Punct, AztecMode::Digit) => &[(31, 5), (30, 5)],
+        (AztecMode::Digit, AztecMode::Upper) => &[(14, 4)],
+        (AztecMode::Digit, AztecMode::Lower) => &[(14, 4), (28, 5)],
+        (AztecMode::Digit, AztecMode::Mixed) => &[(14, 4), (29, 5)],
+        (AztecMode::Digit, AztecMode::Punct) => &[(14, 4), (29, 5), (30, 5)],
+        _ => &[],
+    }
+}
+
+fn latch_width(from: AztecMode, to: AztecMode) -> u32 {
+    latch(from, to).iter().map(|(_, width)| width).sum()
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: u32, width: u32) {
+    for i in (0..width).rev() {
+        bits.push((value >> i) & 1 == 1);
+    }
+}
+
+/// Greedy high-level encoding: stays in the current mode while possible,
+/// uses P/S and U/S for single characters and latches when the next
+/// character belongs to the same mode. Bytes outside every mode go through
+/// binary shift.
+pub fn high_level_encode(data: &[u8]) -> Vec<bool> {
+    let mut bits: Vec<bool> = Vec::new();
+    let mut mode = AztecMode::Upper;
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        let pair = data.get(i + 1).and_then(|next| pair_code(byte, *next));
+        if mode == AztecMode::Punct {
+            if let Some(code) = pair {
+                push_bits(&mut bits, code, 5);
+                i += 2;
+                continue;
+            }
+        }
+        if let Some(code) = mode_code(mode, byte) {
+            push_bits(&mut bits, code, code_width(mode));
+            i += 1;
+            continue;
+        }
+        if let Some(code) = pair {
+            push_bits(&mut bits, 0, code_width(mode));
+            push_bits(&mut bits, code, 5);
+            i += 2;
+            continue;
+        }
+        let candidates: Vec<AztecMode> = AZTEC_MODES
+            .iter()
+            .copied()
+            .filter(|candidate| mode_code(*candidate, byte).is_some())
+            .collect();
+        if candidates.is_empty() {
+            let mut end = i;
+            while end < data.len()
+                && end - i < AZTEC_MAX_BINARY_SHIFT
+                && AZTEC_MODES
+                    .iter()
+                    .all(|candidate| mode_code(*candidate, data[end]).is_none())
+            {
+                end += 1;
+            }
+            if mode == AztecMode::Digit || mode == AztecMode::Punct {
+                for (value, width) in latch(mode, AztecMode::Upper) {
+                    push_bits(&mut bits, *value, *width);
+                }
+                mode = AztecMode::Upper;
+            }
+            push_bits(&mut bits, 31, 5);
+            let length = (end - i) as u32;
+            if length <= 31 {
+                push_bits(&mut bits, length, 5);
+            } else {
+                push_bits(&mut bits, 0, 5);
+                push_bits(&mut bits, length - 31, 11);
+            }
+            for value in &data[i..end] {
+                push_bits(&mut bits, *value as u32, 8);
+            }
+            i = end;
+            continue;
+        }
+        let next = data.get(i + 1).copied();
+        let fits_next =
+            |candidate: AztecMode| next.is_some_and(|next| mode_code(candidate, next).is_some());
+        let target = *candidates
+            .iter()
+            .min_by_key(|candidate| (!fits_next(**candidate), latch_width(mode, **candidate)))
+            .unwrap();
+        let code = mode_code(target, byte).unwrap();
+        if !fits_next(target) {
+            if target == AztecMode::Punct {
+                push_bits(&mut bits, 0, code_width(mode));
+                push_bits(&mut bits, code, 5);
+                i += 1;
+                continue;
+            }
+            if target == AztecMode::Upper && mode == AztecMode::Lower {
+                push_bits(&mut bits, 28, 5);
+                push_bits(&mut bits, code, 5);
+                i += 1;
+                continue;
+            }
+            if target == AztecMode::Upper && mode == AztecMode::Digit {
+                push_bits(&mut bits, 15, 4);
+                push_bits(&mut bits, code, 5);
+                i += 1;
+                continue;
+            }
+        }
+        for (value, width) in latch(mode, target) {
+            push_bits(&mut bits, *value, *width);
+        }
+        mode = target;
+    }
+    bits
+}
+
+pub fn aztec_galois_field(word_size: usize) -> GaloisField {
+    let (_, primitive, size) = AZTEC_GALOIS_FIELDS
+        .iter()
+        .find(|(bits, _, _)| *bits == word_size)
+        .expect("no Aztec field for this word size");
+    GaloisField::new(*primitive, *size, 1)
+}
+
+/// Splits the bit stream into words, inserting a complementary bit after
+/// any `word_size - 1` identical leading bits so no word is all zeros or
+/// all ones. The last word is padded with ones; empty input still gets one
+/// padding word, as the mode message counts at least one data word.
+pub fn stuff_bits(bits: &[bool], word_size: usize) -> Vec<bool> {
+    let mut stuffed: Vec<bool> = Vec::new();
+    let mask: u32 = (1 << word_size) - 2;
+    let mut i = 0;
+    while i < bits.len() || stuffed.is_empty() {
+        let mut word: u32 = 0;
+        for j in 0..word_size {
+            if i + j >= bits.len() || bits[i + j] {
+                word |= 1 << (word_size - 1 - j);
+            }
+        }
+        if word & mask == mask {
+            push_bits(&mut stuffed, word & mask, word_size as u32);
+            i += word_size - 1;
+        } else if word & mask == 0 {
+            push_bits(&mut stuffed, word | 1, word_size as u32);
+            i += word_size - 1;
+        } else {
+            push_bits(&mut stuffed, word, word_size as u32);
+            i += word_size;
+        }
+    }
+    stuffed
+}
+
+fn to_words(bits: &[bool], word_size: usize) -> Vec<u32> {
+    bits.chunks(word_size)
+        .map(|chunk| chunk.iter().fold(0, |word, bit| (word << 1) | *bit as u32))
+        .collect()
+}
+
+/// Appends Reed-Solomon check words so the result fills `total_bits`,
+/// left-padded with zeros to a whole number of words.
+pub fn generate_check_words(bits: &[bool], total_bits: usize, word_size: usize) -> Vec<bool> {
+    let field = aztec_galois_field(word_size);
+    let words = to_words(bits, word_size);
+    let total_words = total_bits / word_size;
+    let check_words = field.remainder(&words, total_words - words.len());
+    let mut message: Vec<bool> = vec![false; total_bits % word_size];
+    for word in words.iter().chain(check_words.iter()) {
+        push_bits(&mut message, *word, word_size as u32);
+    }
+    message
+}
+
+pub fn mode_message(compact: bool, layers: usize, data_words: usize) -> Vec<bool> {
+    let mut bits: Vec<bool> = Vec::new();
+    if compact {
+        push_bits(&mut bits, layers as u32 - 1, 2);
+        push_bits(&mut bits, data_words as u32 - 1, 6);
+        generate_check_words(&bits, 28, 4)
+    } else {
+        push_bits(&mut bits, layers as u32 - 1, 5);
+        push_bits(&mut bits, data_words as u32 - 1, 11);
+        generate_check_words(&bits, 40, 4)
+    }
+}
+
+fn total_bits_in_layers(layers: usize, compact: bool) -> usize {
+    ((if compact { 88 } else { 112 }) + 16 * layers) * layers
+}
+
+pub struct AztecCode {
+    pub compact: bool,
+    pub layers: u8,
+    pub data_words: u16,
+    pub size: u32,
+    aztec: Matrix<u8>,
+}
+
+impl fmt::Debug for AztecCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        render::fmt_modules(self, f)
+    }
+}
+impl Symbol for AztecCode {
+    fn modules(&self) -> &Matrix<u8> {
+        &self.aztec
+    }
+}
+
+impl AztecCode {
+    /// Encodes `data` in the smallest compact or full-range symbol leaving
+    /// at least `min_error_percent` of the data size for check words.
+    pub fn new(data: &str, min_error_percent: u8) -> Result<AztecCode, Error> {
+        AztecCode::from_bytes(data.as_bytes(), min_error_percent)
+    }
+    pub fn from_bytes(data: &[u8], min_error_percent: u8) -> Result<AztecCode, Error> {
+        let bits = high_level_encode(data);
+        let error_bits = bits.len() * min_error_percent as usize / 100 + 11;
+        let total_size_bits = bits.len() + error_bits;
+        let mut chosen: Option<(bool, usize, Vec<bool>)> = None;
+        for i in 0..=32 {
+            let compact = i <= 3;
+            let layers = if compact { i + 1 } else { i };
+            let total_bits = total_bits_in_layers(layers, compact);
+            if total_size_bits > total_bits {
+                continue;
+            }
+            let word_size = AZTEC_WORD_SIZE[layers];
+            let stuffed = stuff_bits(&bits, word_size);
+            if compact && stuffed.len() > word_size * 64 {
+                continue;
+            }
+            if stuffed.len() + error_bits <= total_bits - total_bits % word_size {
+                chosen = Some((compact, layers, stuffed));
+                break;
+            }
+        }
+        let (compact, layers, stuffed) = chosen.ok_or(Error::DataTooLong)?;
+        let word_size = AZTEC_WORD_SIZE[layers];
+        let data_words = stuffed.len() / word_size;
+        let message =
+            generate_check_words(&stuffed, total_bits_in_layers(layers, compact), word_size);
+
+        let base_size = (if compact { 11 } else { 14 }) + layers * 4;
+        let mut alignment_map: Vec<usize> = (0..base_size).collect();
+        let mut size = base_size;
+        if !compact {
+            size = base_size + 1 + 2 * ((base_size / 2 - 1) / 15);
+            let base_center = base_size / 2;
+            let center = size / 2;
+            for i in 0..base_center {
+                let offset = i + i / 15;
+                alignment_map[base_center - i - 1] = center - offset - 1;
+                alignment_map[base_center + i] = center + offset + 1;
+            }
+        }
+        let mut aztec = AztecCode {
+            compact,
+            layers: layers as u8,
+            data_words: data_words as u16,
+            size: size as u32,
+            aztec: Matrix::create(size as u32, size as u32, &1),
+        };
+        aztec.insert_data(&message, &alignment_map, base_size);
+        aztec.create_mode_message(&mode_message(compact, layers, data_words));
+        aztec.create_bullseye();
+        if !compact {
+            aztec.create_reference_grid(base_size);
+        }
+        Ok(aztec)
+    }
+    fn dark(&mut self, x: usize, y: usize) {
+        self.aztec.put((x as u32, y as u32), 0)
+    }
+    /// Lays the message out in two-module-wide rings spiralling inwards,
+    /// starting from the outermost layer.
+    fn insert_data(&mut self, message: &[bool], alignment_map: &[usize], base_size: usize) {
+        let layers = self.layers as usize;
+        let mut row_offset = 0;
+        for i in 0..layers {
+            let row_size = (layers - i) * 4 + if self.compact { 9 } else { 12 };
+            for j in 0..row_size {
+                let column_offset = j * 2;
+                for k in 0..2 {
+                    let near = alignment_map[i * 2 + k];
+                    let far = alignment_map[base_size - 1 - i * 2 - k];
+                    let along = alignment_map[i * 2 + j];
+                    let back = alignment_map[base_size - 1 - i * 2 - j];
+                    if message[row_offset + column_offset + k] {
+                        self.dark(near, along);
+                    }
+                    if message[row_offset + row_size * 2 + column_offset + k] {
+                        self.dark(along, far);
+                    }
+                    if message[row_offset + row_size * 4 + column_offset + k] {
+                        self.dark(far, back);
+                    }
+                    if message[row_offset + row_size * 6 + column_offset + k] {
+                        self.dark(back, near);
+                    }
+                }
+            }
+            row_offset += row_size * 8;
+        }
+    }
+    fn create_mode_message(&mut self, mode_message: &[bool]) {
+        let center = self.size as usize / 2;
+        if self.compact {
+            for i in 0..7 {
+                let offset = center - 3 + i;
+                if mode_message[i] {
+                    self.dark(offset, center - 5);
+                }
+                if mode_message[i + 7] {
+                    self.dark(center + 5, offset);
+                }
+                if mode_message[20 - i] {
+                    self.dark(offset, center + 5);
+                }
+                if mode_message[27 - i] {
+                    self.dark(center - 5, offset);
+                }
+            }
+        } else {
+            for i in 0..10 {
+                let offset = center - 5 + i + i / 5;
+                if mode_message[i] {
+                    self.dark(offset, center - 7);
+                }
+                if mode_message[i + 10] {
+                    self.dark(center + 7, offset);
+                }
+                if mode_message[29 - i] {
+                    self.dark(offset, center + 7);
+                }
+                if mode_message[39 - i] {
+                    self.dark(center - 7, offset);
+                }
+            }
+        }
+    }
+    /// Concentric dark rings around the center plus the three orientation
+    /// marks in the corners of the mode message ring.
+    fn create_bullseye(&mut self) {
+        let center = self.size as usize / 2;
+        let radius = if self.compact { 5 } else { 7 };
+        for i in (0..radius).step_by(2) {
+            for j in (center - i)..=(center + i) {
+                self.dark(j, center - i);
+                self.dark(j, center + i);
+                self.dark(center - i, j);
+                self.dark(center + i, j);
+            }
+        }
+        self.dark(center - radius, center - radius);
+        self.dark(center - radius + 1, center - radius);
+        self.dark(center - radius, center - radius + 1);
+        self.dark(center + radius, center - radius);
+        self.dark(center + radius, center - radius + 1);
+        self.dark(center + radius, center + radius - 1);
+    }
+    fn create_reference_grid(&mut self, base_size: usize) {
+        let size = self.size as usize;
+        let center = size / 2;
+        let mut i = 0;
+        let mut j = 0;
+        while i < base_size / 2 - 1 {
+            for k in ((center & 1)..size).step_by(2) {
+                self.dark(center - j, k);
+                self.dark(center + j, k);
+                self.dark(k, center - j);
+                self.dark(k, center + j);
+            }
+            i += 15;
+            j += 16;
+        }
+    }
+}
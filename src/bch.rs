@@ -0,0 +1,83 @@
+/// Generator of the BCH(15,5) format information code.
+pub const FORMAT_GENERATOR: u32 = 0b101_0011_0111;
+/// XOR applied to format information so it is never all light.
+pub const FORMAT_MASK: u32 = 0b101_0100_0001_0010;
+/// XOR QR Model 1 applies instead, telling the two models apart.
+pub const FORMAT_MASK_MODEL1: u32 = 0b010_1000_0010_0101;
+/// Generator of the BCH(18,6) version information code.
+pub const VERSION_GENERATOR: u32 = 0b1_1111_0010_0101;
+
+/// `data` followed by the remainder of its division by `generator`.
+const fn encode(data: u32, check_bits: u32, generator: u32) -> u32 {
+    let mut remainder = data << check_bits;
+    let mut bit = 32 - remainder.leading_zeros();
+    while bit > check_bits {
+        bit -= 1;
+        if remainder & (1 << bit) != 0 {
+            remainder ^= generator << (bit - check_bits);
+        }
+    }
+    (data << check_bits) | remainder
+}
+
+/// Format information of 5 data bits, the error level indicator then the
+/// mask, with `FORMAT_MASK` applied.
+pub const fn format_codeword(data: u32) -> u32 {
+    encode(data, 10, FORMAT_GENERATOR) ^ FORMAT_MASK
+}
+
+/// Format information of a QR Model 1 symbol, with `FORMAT_MASK_MODEL1`
+/// applied.
+pub const fn model1_format_codeword(data: u32) -> u32 {
+    encode(data, 10, FORMAT_GENERATOR) ^ FORMAT_MASK_MODEL1
+}
+
+/// Version information of versions 7 to 40.
+pub const fn version_codeword(version: u8) -> u32 {
+    encode(version as u32, 12, VERSION_GENERATOR)
+}
+
+/// Every valid format codeword, indexed by its data bits.
+pub const FORMAT_CODEWORDS: [u32; 32] = {
+    let mut codewords = [0; 32];
+    let mut data = 0;
+    while data < 32 {
+        codewords[data] = format_codeword(data as u32);
+        data += 1;
+    }
+    codewords
+};
+
+/// Every valid version codeword, versions 7 to 40.
+pub const VERSION_CODEWORDS: [u32; 34] = {
+    let mut codewords = [0; 34];
+    let mut i = 0;
+    while i < 34 {
+        codewords[i] = version_codeword(i as u8 + 7);
+        i += 1;
+    }
+    codewords
+};
+
+/// Index and bit distance of the codeword nearest to `received`, `None`
+/// beyond the 3 bit errors both codes correct.
+fn nearest(codewords: &[u32], received: u32) -> Option<(usize, u32)> {
+    codewords
+        .iter()
+        .map(|codeword| (codeword ^ received).count_ones())
+        .enumerate()
+        .min_by_key(|(_, distance)| *distance)
+        .filter(|(_, distance)| *distance <= 3)
+}
+
+/// Data bits of the format codeword nearest to `received` and the bit
+/// errors corrected.
+pub fn decode_format(received: u32) -> Option<(u32, u32)> {
+    nearest(&FORMAT_CODEWORDS, received).map(|(data, distance)| (data as u32, distance))
+}
+
+/// Version of the version codeword nearest to `received` and the bit
+/// errors corrected.
+pub fn decode_version(received: u32) -> Option<(u8, u32)> {
+    nearest(&VERSION_CODEWORDS, received).map(|(i, distance)| (i as u8 + 7, distance))
+}
@@ -0,0 +1,158 @@
+use crate::error::Error;
+use crate::qr_code::Matrix;
+
+/// Pixel value of dark pixels in a binarized image.
+pub const DARK: u8 = 0;
+/// Pixel value of light pixels in a binarized image.
+pub const LIGHT: u8 = 255;
+
+/// Threshold between the two classes of the histogram with the largest
+/// variance between them; pixels below it are dark. The middle of the best
+/// range when several thresholds tie, as they do between two gray levels.
+pub fn otsu_threshold(image: &Matrix<u8>) -> u8 {
+    let (width, height) = image.size();
+    let mut histogram = [0u64; 256];
+    for y in 0..height {
+        for x in 0..width {
+            histogram[image.get((x, y)) as usize] += 1;
+        }
+    }
+    let total = (width as u64 * height as u64) as f64;
+    let sum: f64 = (0..256)
+        .map(|value| value as f64 * histogram[value] as f64)
+        .sum();
+    let (mut below, mut below_sum) = (0.0, 0.0);
+    let mut best = (0.0, 0, 0);
+    for threshold in 1..256 {
+        below += histogram[threshold - 1] as f64;
+        below_sum += (threshold - 1) as f64 * histogram[threshold - 1] as f64;
+        let above = total - below;
+        if below == 0.0 || above == 0.0 {
+            continue;
+        }
+        let difference = below_sum / below - (sum - below_sum) / above;
+        let variance = below * above * difference * difference;
+        if variance > best.0 {
+            best = (variance, threshold, threshold);
+        } else if variance == best.0 && best.2 == threshold - 1 {
+            best.2 = threshold;
+        }
+    }
+    ((best.1 + best.2) / 2) as u8
+}
+
+/// Every pixel against one threshold from `otsu_threshold`.
+pub fn otsu(image: &Matrix<u8>) -> Matrix<u8> {
+    let threshold = otsu_threshold(image);
+    let (width, height) = image.size();
+    let mut binary = Matrix::create(width, height, &LIGHT);
+    for y in 0..height {
+        for x in 0..width {
+            if image.get((x, y)) < threshold {
+                binary.put((x, y), DARK);
+            }
+        }
+    }
+    binary
+}
+
+/// Sums of pixel values and of their squares over every rectangle from the
+/// origin, one row and column larger than the image.
+struct Integral {
+    width: usize,
+    sums: Vec<u64>,
+    squares: Vec<u64>,
+}
+
+impl Integral {
+    fn new(image: &Matrix<u8>) -> Integral {
+        let (width, height) = image.size();
+        let (width, height) = (width as usize + 1, height as usize + 1);
+        let mut sums = vec![0u64; width * height];
+        let mut squares = vec![0u64; width * height];
+        for y in 1..height {
+            let (mut row, mut row_squares) = (0, 0);
+            for x in 1..width {
+                let value = image.get(((x - 1) as u32, (y - 1) as u32)) as u64;
+                row += value;
+                row_squares += value * value;
+                sums[y * width + x] = sums[(y - 1) * width + x] + row;
+                squares[y * width + x] = squares[(y - 1) * width + x] + row_squares;
+            }
+        }
+        Integral {
+            width,
+            sums,
+            squares,
+        }
+    }
+    /// Mean and standard deviation of the `window` square centered on
+    /// `(x, y)`, cut at the image edges.
+    fn window(&self, (x, y): (u32, u32), window: u32) -> (f64, f64) {
+        let height = self.sums.len() / self.width;
+        let half = (window / 2) as usize;
+        let (x, y) = (x as usize, y as usize);
+        let (left, top) = (x.saturating_sub(half), y.saturating_sub(half));
+        let (right, bottom) = (
+            (x + half + 1).min(self.width - 1),
+            (y + half + 1).min(height - 1),
+        );
+        let area = |table: &[u64]| {
+            table[bottom * self.width + right] + table[top * self.width + left]
+                - table[top * self.width + right]
+                - table[bottom * self.width + left]
+        };
+        let count = ((right - left) * (bottom - top)) as f64;
+        let mean = area(&self.sums) as f64 / count;
+        let variance = area(&self.squares) as f64 / count - mean * mean;
+        (mean, variance.max(0.0).sqrt())
+    }
+}
+
+fn check_window(window: u32) -> Result<(), Error> {
+    if window < 3 || window.is_multiple_of(2) {
+        return Err(Error::InvalidOption("window must be odd and at least 3"));
+    }
+    Ok(())
+}
+
+/// Each pixel against a threshold of its own from the `window` square
+/// around it.
+fn local(
+    image: &Matrix<u8>,
+    window: u32,
+    threshold: impl Fn(f64, f64) -> f64,
+) -> Result<Matrix<u8>, Error> {
+    check_window(window)?;
+    let integral = Integral::new(image);
+    let (width, height) = image.size();
+    let mut binary = Matrix::create(width, height, &LIGHT);
+    for y in 0..height {
+        for x in 0..width {
+            let (mean, deviation) = integral.window((x, y), window);
+            if (image.get((x, y)) as f64) < threshold(mean, deviation) {
+                binary.put((x, y), DARK);
+            }
+        }
+    }
+    Ok(binary)
+}
+
+/// Dark where a pixel is more than `offset` below the mean of the `window`
+/// square around it. The offset keeps flat areas light.
+pub fn block_mean(image: &Matrix<u8>, window: u32, offset: u8) -> Result<Matrix<u8>, Error> {
+    local(image, window, |mean, _| mean - offset as f64)
+}
+
+/// Sauvola's threshold `mean * (1 + k * (deviation / 128 - 1))` over the
+/// `window` square around each pixel. Larger `k`, from `0.0` to `1.0`,
+/// darkens less in low contrast areas; `0.2` to `0.5` is usual.
+pub fn sauvola(image: &Matrix<u8>, window: u32, k: f32) -> Result<Matrix<u8>, Error> {
+    if !(0.0..=1.0).contains(&k) {
+        return Err(Error::InvalidOption("Sauvola k is 0.0..1.0"));
+    }
+    let k = k as f64;
+    local(image, window, |mean, deviation| {
+        mean * (1.0 + k * (deviation / 128.0 - 1.0))
+    })
+}
@@ -0,0 +1,154 @@
+use crate::render::{padded_rows, Symbol};
+
+/// Grayscale raster, one byte per pixel row by row, `0` black and `255` white.
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Bitmap {
+    /// `scale` pixels per module, quiet zone included.
+    pub fn from_symbol(symbol: &dyn Symbol, scale: u32) -> Bitmap {
+        let rows = padded_rows(symbol, symbol.quiet_zone());
+        let width = rows[0].len() as u32 * scale;
+        let height = rows.len() as u32 * scale;
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for row in &rows {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|dark| vec![if *dark { 0 } else { 255 }; scale as usize])
+                .collect();
+            for _ in 0..scale {
+                pixels.extend_from_slice(&line);
+            }
+        }
+        Bitmap {
+            width,
+            height,
+            pixels,
+        }
+    }
+    pub fn get(&self, x: u32, y: u32) -> u8 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+    /// 8-bit grayscale PNG.
+    pub fn to_png(&self) -> Vec<u8> {
+        encode_png(self.width, self.height, 0, &self.pixels)
+    }
+}
+
+/// Raster with alpha, four bytes per pixel (red, green, blue, alpha).
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn get(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+            self.pixels[index + 3],
+        ]
+    }
+    /// 8-bit RGBA PNG.
+    pub fn to_png(&self) -> Vec<u8> {
+        encode_png(self.width, self.height, 6, &self.pixels)
+    }
+}
+
+/// PNG of 8-bit samples, `color_type` 0 (gray) or 6 (RGBA). The image data
+/// is zlib wrapped but stored without compression, which keeps the writer
+/// free of dependencies.
+fn encode_png(width: u32, height: u32, color_type: u8, samples: &[u8]) -> Vec<u8> {
+    let channels = if color_type == 6 { 4 } else { 1 };
+    let stride = (width * channels) as usize;
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in samples.chunks(stride) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    let mut header = Vec::new();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, color_type, 0, 0, 0]);
+    let mut png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    png_chunk(&mut png, b"IHDR", &header);
+    png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn png_chunk(png: &mut Vec<u8>, name: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(name);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xffff_ffff;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b): (u32, u32) = (1, 0);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// zlib stream of stored deflate blocks (at most 65535 bytes each).
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = if data.is_empty() {
+        vec![&[]]
+    } else {
+        data.chunks(65535).collect()
+    };
+    for (i, block) in blocks.iter().enumerate() {
+        zlib.push((i == blocks.len() - 1) as u8);
+        zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn base64(data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let value = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(BASE64[(value >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
@@ -0,0 +1,68 @@
+use crate::config::{ByteNameOfMode, ErrorLevel};
+use crate::utils;
+
+/// Data codewords of a symbol, error correction codewords excluded.
+pub fn data_codewords(version: u8, error_level: ErrorLevel) -> u32 {
+    utils::get_codewords_number(version, &error_level)
+}
+
+/// Bits available to segments, mode indicators and length fields included.
+pub fn data_bits(version: u8, error_level: ErrorLevel) -> u32 {
+    data_codewords(version, error_level) * 8
+}
+
+/// Characters of `mode` a single segment can hold: digits, alphanumeric
+/// characters, bytes or Kanji characters.
+pub fn characters(mode: ByteNameOfMode, version: u8, error_level: ErrorLevel) -> u32 {
+    utils::full_capacity(version, &error_level, &mode)
+}
+
+/// Run of `characters` encoded in a single mode.
+#[derive(Debug, Copy, Clone)]
+pub struct Segment {
+    pub mode: ByteNameOfMode,
+    pub characters: u32,
+}
+
+impl Segment {
+    /// Mode indicator, length field and data bits of the segment in a
+    /// symbol of `version`.
+    pub fn bits(&self, version: u8) -> u32 {
+        let characters = self.characters;
+        let data = match self.mode {
+            ByteNameOfMode::Numeric => characters / 3 * 10 + [0, 4, 7][(characters % 3) as usize],
+            ByteNameOfMode::Alpha => characters / 2 * 11 + characters % 2 * 6,
+            ByteNameOfMode::Byte => characters * 8,
+            ByteNameOfMode::Kanji => characters * 13,
+        };
+        4 + utils::get_len_bit(&self.mode, version) as u32 + data
+    }
+}
+
+/// Bits `segments` take in a symbol of `version`, terminator excluded.
+pub fn bits(segments: &[Segment], version: u8) -> u32 {
+    segments.iter().map(|segment| segment.bits(version)).sum()
+}
+
+pub fn fits(segments: &[Segment], version: u8, error_level: ErrorLevel) -> bool {
+    segments
+        .iter()
+        .all(|segment| segment.characters < 1 << utils::get_len_bit(&segment.mode, version))
+        && bits(segments, version) <= data_bits(version, error_level)
+}
+
+/// Smallest version holding `data` as one byte segment at `error_level`,
+/// as the encoder writes it.
+pub fn smallest_version_for(data: &str, error_level: ErrorLevel) -> Option<u8> {
+    let segment = [Segment {
+        mode: ByteNameOfMode::Byte,
+        characters: data.len() as u32,
+    }];
+    (1..=40).find(|version| fits(&segment, *version, error_level))
+}
+
+/// Bytes still fitting a symbol of `version` after `data`, `None` when
+/// `data` does not fit.
+pub fn characters_left(data: &str, version: u8, error_level: ErrorLevel) -> Option<u32> {
+    characters(ByteNameOfMode::Byte, version, error_level).checked_sub(data.len() as u32)
+}
@@ -0,0 +1,207 @@
+use crate::error::Error;
+
+/// Lowest contrast ratio (WCAG definition, 1 to 21) accepted between any
+/// foreground color and the background.
+pub const MIN_CONTRAST: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgba {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+impl Rgba {
+    pub const BLACK: Rgba = Rgba::rgb(0, 0, 0);
+    pub const WHITE: Rgba = Rgba::rgb(255, 255, 255);
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Rgba {
+        Rgba {
+            red,
+            green,
+            blue,
+            alpha: 255,
+        }
+    }
+    pub fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+    /// Relative luminance of sRGB, alpha ignored.
+    pub fn luminance(&self) -> f32 {
+        let linear = |channel: u8| {
+            let value = channel as f32 / 255.0;
+            if value <= 0.03928 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.red) + 0.7152 * linear(self.green) + 0.0722 * linear(self.blue)
+    }
+    fn mix(&self, other: &Rgba, t: f32) -> Rgba {
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Rgba {
+            red: channel(self.red, other.red),
+            green: channel(self.green, other.green),
+            blue: channel(self.blue, other.blue),
+            alpha: channel(self.alpha, other.alpha),
+        }
+    }
+    /// This color painted over `background`.
+    pub fn over(&self, background: &Rgba) -> Rgba {
+        let mut color = background.mix(self, self.alpha as f32 / 255.0);
+        color.alpha = 255;
+        color
+    }
+}
+
+pub fn contrast_ratio(a: &Rgba, b: &Rgba) -> f32 {
+    let (a, b) = (a.luminance(), b.luminance());
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Fill of one component. Gradients span the whole symbol, quiet zone
+/// included.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paint {
+    Solid(Rgba),
+    /// From `from` to `to` along `angle` degrees, clockwise from left to right.
+    Linear {
+        from: Rgba,
+        to: Rgba,
+        angle: f32,
+    },
+    /// From `center` in the middle of the symbol to `edge` at half its width.
+    Radial {
+        center: Rgba,
+        edge: Rgba,
+    },
+}
+
+impl Paint {
+    pub fn stops(&self) -> Vec<Rgba> {
+        match self {
+            Paint::Solid(color) => vec![*color],
+            Paint::Linear { from, to, .. } => vec![*from, *to],
+            Paint::Radial { center, edge } => vec![*center, *edge],
+        }
+    }
+    /// Color at `(x, y)`, both from `0.0` to `1.0` across the symbol.
+    pub fn at(&self, x: f32, y: f32) -> Rgba {
+        match self {
+            Paint::Solid(color) => *color,
+            Paint::Linear { from, to, angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let t = ((x - 0.5) * cos + (y - 0.5) * sin) / (cos.abs() + sin.abs()) + 0.5;
+                from.mix(to, t.clamp(0.0, 1.0))
+            }
+            Paint::Radial { center, edge } => {
+                let t = ((x - 0.5).powi(2) + (y - 0.5).powi(2)).sqrt() * 2.0;
+                center.mix(edge, t.min(1.0))
+            }
+        }
+    }
+    /// SVG gradient definition with `id`, `size` user units wide, or `None`
+    /// for a solid color.
+    pub fn svg_gradient(&self, id: &str, size: u32) -> Option<String> {
+        let stop = |offset: u8, color: &Rgba| {
+            format!(
+                "<stop offset=\"{}\" stop-color=\"{}\" stop-opacity=\"{}\"/>",
+                offset,
+                color.hex(),
+                crate::render::number(color.alpha as f32 / 255.0)
+            )
+        };
+        let half = size as f32 / 2.0;
+        match self {
+            Paint::Solid(_) => None,
+            Paint::Linear { from, to, angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                // Same ends as `at`: the gradient meets the far corners.
+                let reach = half * (cos.abs() + sin.abs());
+                let number = crate::render::number;
+                Some(format!(
+                    "<linearGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">{}{}</linearGradient>",
+                    id,
+                    number(half - cos * reach),
+                    number(half - sin * reach),
+                    number(half + cos * reach),
+                    number(half + sin * reach),
+                    stop(0, from),
+                    stop(1, to)
+                ))
+            }
+            Paint::Radial { center, edge } => Some(format!(
+                "<radialGradient id=\"{0}\" gradientUnits=\"userSpaceOnUse\" cx=\"{1}\" cy=\"{1}\" r=\"{1}\">{2}{3}</radialGradient>",
+                id,
+                crate::render::number(half),
+                stop(0, center),
+                stop(1, edge)
+            )),
+        }
+    }
+    /// `fill` and `fill-opacity` attributes, referencing gradient `id`.
+    pub fn svg_fill(&self, id: &str) -> String {
+        match self {
+            Paint::Solid(color) => format!(
+                "fill=\"{}\" fill-opacity=\"{}\"",
+                color.hex(),
+                crate::render::number(color.alpha as f32 / 255.0)
+            ),
+            _ => format!("fill=\"url(#{})\"", id),
+        }
+    }
+}
+
+/// Paint of every component; a `None` background is transparent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Colors {
+    pub data: Paint,
+    pub finder_outer: Paint,
+    pub finder_inner: Paint,
+    pub alignment: Paint,
+    pub background: Option<Rgba>,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Colors {
+            data: Paint::Solid(Rgba::BLACK),
+            finder_outer: Paint::Solid(Rgba::BLACK),
+            finder_inner: Paint::Solid(Rgba::BLACK),
+            alignment: Paint::Solid(Rgba::BLACK),
+            background: Some(Rgba::WHITE),
+        }
+    }
+}
+
+impl Colors {
+    /// Lowest contrast ratio between any foreground color and the
+    /// background, a transparent background counting as white paper.
+    /// Refused below `MIN_CONTRAST` and when a foreground color is lighter
+    /// than the background, which many readers cannot decode.
+    pub fn check_contrast(&self) -> Result<f32, Error> {
+        let background = self.background.unwrap_or(Rgba::WHITE).over(&Rgba::WHITE);
+        let mut lowest = f32::MAX;
+        for paint in [
+            &self.data,
+            &self.finder_outer,
+            &self.finder_inner,
+            &self.alignment,
+        ] {
+            for stop in paint.stops() {
+                let stop = stop.over(&background);
+                if stop.luminance() > background.luminance() {
+                    return Err(Error::InvalidOption(
+                        "foreground lighter than the background",
+                    ));
+                }
+                lowest = lowest.min(contrast_ratio(&stop, &background));
+            }
+        }
+        if lowest < MIN_CONTRAST {
+            return Err(Error::InvalidOption("contrast too low for scanning"));
+        }
+        Ok(lowest)
+    }
+}
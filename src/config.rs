@@ -23,20 +23,66 @@ pub enum Mask {
     _110,
     _111,
 }
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Model {
+    Model1,
+    Model2,
+}
+/// Error levels from the weakest to the strongest; the declaration order of
+/// `ErrorLevel` follows the format information bits instead.
+pub const ERROR_LEVELS: [ErrorLevel; 4] =
+    [ErrorLevel::L, ErrorLevel::M, ErrorLevel::Q, ErrorLevel::H];
+/// How the version and error level of a symbol are chosen from the
+/// requested ones.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// Smallest version at the requested error level.
+    SmallestVersion,
+    /// Smallest version at the requested error level, then the strongest
+    /// level that still fits that version.
+    BoostLevel,
+    /// Exactly the requested version, at the strongest level (the requested
+    /// one at least) that fits.
+    FixedVersion,
+}
+/// Role of a single module; codeword indices follow the final interleaved
+/// stream and bit `7` is the most significant bit of a codeword.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ModuleKind {
+    Empty,
+    Finder,
+    Separator,
+    Timing,
+    Alignment,
+    Format,
+    Version,
+    DarkModule,
+    Extension,
+    Data { codeword: u16, bit: u8 },
+    ErrorCorrection { codeword: u16, bit: u8 },
+    Remainder,
+}
+pub const MASKS: [Mask; 8] = [
+    Mask::_000,
+    Mask::_001,
+    Mask::_010,
+    Mask::_011,
+    Mask::_100,
+    Mask::_101,
+    Mask::_110,
+    Mask::_111,
+];
 
 pub type EntityPolynomial = (i16, i16, i16);
 pub type ReedSolomonOptions = (i8, ErrorLevel);
 
-// format, version
-pub const VERSION_FORMAT_POLYNOMIAL: [[u8; 8]; 2] =
-    [[10, 8, 5, 4, 2, 1, 0, 0], [12, 11, 10, 9, 8, 5, 2, 0]];
-pub const FORMAT_STRING_XOR_VALUE: &str = "101010000010010";
 pub const LEVEL_INDICATOR: [u8; 4] = [1, 3, 0, 2];
 pub const BLANK_FILLER: [u8; 2] = [236, 17];
 pub const ALIGNMENT_LOCATION_BASE: [u32; 2] = [6, 18];
 pub const BASE_SIZE: u32 = 21;
 
 pub const MODULO_BYTE_WISE: u32 = 285;
+#[allow(non_snake_case)]
 pub fn MODE_INDICATOR(mode: &ByteNameOfMode) -> String {
     match mode {
         ByteNameOfMode::Numeric => String::from("0001"),
@@ -69,26 +115,48 @@ pub const TABLE_EC: [[(u8, u8); 4]; 40] = [
     [(28, 8), (26, 17), (28, 23), (30, 25)],
     [(28, 9), (28, 17), (30, 23), (24, 34)],
     [(30, 9), (28, 18), (30, 25), (30, 30)],
-    [(30, 1), (28, 20), (30, 27), (30, 32)],
-    [(26, 1), (28, 21), (30, 29), (30, 35)],
-    [(28, 1), (28, 23), (28, 34), (30, 37)],
-    [(30, 1), (28, 25), (30, 34), (30, 40)],
-    [(30, 1), (28, 26), (30, 35), (30, 42)],
-    [(30, 1), (28, 28), (30, 38), (30, 45)],
-    [(30, 1), (28, 29), (30, 40), (30, 48)],
-    [(30, 1), (28, 31), (30, 43), (30, 51)],
-    [(30, 1), (28, 33), (30, 45), (30, 54)],
-    [(30, 1), (28, 35), (30, 48), (30, 57)],
-    [(30, 1), (28, 37), (30, 51), (30, 60)],
-    [(30, 1), (28, 38), (30, 53), (30, 63)],
-    [(30, 2), (28, 40), (30, 56), (30, 66)],
-    [(30, 2), (28, 43), (30, 59), (30, 70)],
-    [(30, 2), (28, 45), (30, 62), (30, 74)],
-    [(30, 2), (28, 47), (30, 65), (30, 77)],
-    [(30, 2), (28, 49), (30, 68), (30, 81)],
+    [(30, 10), (28, 20), (30, 27), (30, 32)],
+    [(26, 12), (28, 21), (30, 29), (30, 35)],
+    [(28, 12), (28, 23), (28, 34), (30, 37)],
+    [(30, 12), (28, 25), (30, 34), (30, 40)],
+    [(30, 13), (28, 26), (30, 35), (30, 42)],
+    [(30, 14), (28, 28), (30, 38), (30, 45)],
+    [(30, 15), (28, 29), (30, 40), (30, 48)],
+    [(30, 16), (28, 31), (30, 43), (30, 51)],
+    [(30, 17), (28, 33), (30, 45), (30, 54)],
+    [(30, 18), (28, 35), (30, 48), (30, 57)],
+    [(30, 19), (28, 37), (30, 51), (30, 60)],
+    [(30, 19), (28, 38), (30, 53), (30, 63)],
+    [(30, 20), (28, 40), (30, 56), (30, 66)],
+    [(30, 21), (28, 43), (30, 59), (30, 70)],
+    [(30, 22), (28, 45), (30, 62), (30, 74)],
+    [(30, 24), (28, 47), (30, 65), (30, 77)],
+    [(30, 25), (28, 49), (30, 68), (30, 81)],
+];
+/// Error correction codewords per block, blocks and data codewords per
+/// block of QR Model 1 versions 1 to 14, in `TABLE_EC` column order. Codewords
+/// the blocks leave over are filled like remainder bits.
+pub const TABLE_EC_MODEL1: [[(u8, u8, u8); 4]; 14] = [
+    [(7, 1, 19), (10, 1, 16), (13, 1, 13), (17, 1, 9)],
+    [(10, 1, 36), (16, 1, 30), (22, 1, 24), (30, 1, 16)],
+    [(15, 1, 57), (28, 1, 44), (36, 1, 36), (48, 1, 24)],
+    [(20, 1, 80), (40, 1, 60), (50, 1, 50), (66, 1, 34)],
+    [(26, 1, 108), (52, 1, 82), (66, 1, 68), (44, 2, 23)],
+    [(34, 1, 136), (32, 2, 53), (42, 2, 43), (56, 2, 29)],
+    [(42, 1, 170), (40, 2, 66), (52, 2, 54), (46, 3, 24)],
+    [(24, 2, 104), (48, 2, 80), (64, 2, 64), (56, 3, 29)],
+    [(30, 2, 123), (60, 2, 93), (50, 3, 52), (68, 3, 34)],
+    [(34, 2, 145), (68, 2, 111), (58, 3, 61), (58, 4, 31)],
+    [(40, 2, 168), (40, 4, 64), (52, 4, 52), (54, 5, 29)],
+    [(46, 2, 192), (46, 4, 73), (58, 4, 61), (62, 5, 33)],
+    [(36, 3, 144), (52, 4, 83), (66, 4, 69), (58, 6, 32)],
+    [(40, 3, 163), (60, 4, 92), (60, 5, 62), (66, 6, 35)],
 ];
+/// Extension pattern on the right edge of a QR Model 1 symbol, 2 modules
+/// wide and 4 high; the bottom edge uses it transposed.
+pub const EXTENSION_PATTERN: [[u8; 2]; 4] = [[0, 0], [1, 0], [1, 0], [0, 0]];
 pub const LENGTH_BITS: [[u8; 3]; 4] = [[10, 12, 14], [9, 11, 13], [8, 16, 16], [8, 10, 12]];
-pub const REMINDER: [usize; 39] = [
-    0, 7, 7, 7, 7, 7, 0, 0, 0, 0, 0, 0, 3, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 3, 3, 3, 3, 3, 3,
-    3, 0, 0, 0, 0, 0, 0,
+pub const REMINDER: [usize; 40] = [
+    0, 7, 7, 7, 7, 7, 0, 0, 0, 0, 0, 0, 0, 3, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 3, 3, 3, 3, 3,
+    3, 3, 0, 0, 0, 0, 0, 0,
 ];
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+/// Outlines of the connected dark regions of a module grid, as closed
+/// polygons of module corner coordinates (`(x, y)`, `y` down). Outer
+/// boundaries run clockwise on screen and holes counter-clockwise, so the
+/// polygons fill correctly with either the nonzero or the even-odd rule.
+/// Only corners are kept, collinear points are dropped, and diagonal
+/// neighbours stay separate regions.
+pub fn trace(rows: &[Vec<bool>]) -> Vec<Vec<(u32, u32)>> {
+    let dark = |x: i64, y: i64| {
+        y >= 0
+            && x >= 0
+            && rows
+                .get(y as usize)
+                .and_then(|row| row.get(x as usize))
+                .is_some_and(|dark| *dark)
+    };
+    // Every side between a dark and a light module, directed with the dark
+    // module on its right.
+    let mut edges: Vec<((i64, i64), (i64, i64))> = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+        for x in 0..row.len() {
+            let (x, y) = (x as i64, y as i64);
+            if !dark(x, y) {
+                continue;
+            }
+            if !dark(x, y - 1) {
+                edges.push(((x, y), (x + 1, y)));
+            }
+            if !dark(x + 1, y) {
+                edges.push(((x + 1, y), (x + 1, y + 1)));
+            }
+            if !dark(x, y + 1) {
+                edges.push(((x + 1, y + 1), (x, y + 1)));
+            }
+            if !dark(x - 1, y) {
+                edges.push(((x, y + 1), (x, y)));
+            }
+        }
+    }
+    let mut outgoing: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, edge) in edges.iter().enumerate() {
+        outgoing.entry(edge.0).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut polygons = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        let mut points = Vec::new();
+        let mut current = first;
+        loop {
+            used[current] = true;
+            let (start, end) = edges[current];
+            let direction = (end.0 - start.0, end.1 - start.1);
+            let next = outgoing[&end]
+                .iter()
+                .copied()
+                .filter(|i| !used[*i] || *i == first)
+                // Where two regions touch at a corner, turning right keeps
+                // them apart.
+                .max_by_key(|i| {
+                    let (next_start, next_end) = edges[*i];
+                    let turn = (next_end.0 - next_start.0, next_end.1 - next_start.1);
+                    turn == (-direction.1, direction.0)
+                })
+                .unwrap();
+            let turn = (
+                edges[next].1 .0 - edges[next].0 .0,
+                edges[next].1 .1 - edges[next].0 .1,
+            );
+            if turn != direction {
+                points.push((end.0 as u32, end.1 as u32));
+            }
+            if next == first {
+                break;
+            }
+            current = next;
+        }
+        // Start each polygon at its first corner in trace order, the top
+        // left corner for outer boundaries.
+        points.rotate_right(1);
+        polygons.push(points);
+    }
+    polygons
+}
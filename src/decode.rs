@@ -0,0 +1,214 @@
+use crate::bch;
+use crate::config::{
+    ByteNameOfMode, ErrorLevel, Mask, ERROR_LEVELS, LEVEL_INDICATOR, MASKS, REMINDER,
+};
+use crate::error::Error;
+use crate::locate;
+use crate::polynomial::qr_galois_field;
+use crate::qr_code::{format_positions, Matrix, QRcode};
+use crate::reed_solomon::ReedSolomonData;
+use crate::sample;
+use crate::utils;
+
+const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Payload read back from a symbol.
+#[derive(Debug, Clone)]
+pub struct Decoded {
+    pub version: u8,
+    pub error_level: ErrorLevel,
+    pub mask: Mask,
+    pub bytes: Vec<u8>,
+    /// `bytes` as UTF-8, `None` when they are not valid UTF-8.
+    pub text: Option<String>,
+    /// Codewords repaired by error correction, all blocks together.
+    pub corrected: usize,
+}
+
+/// Error level and mask of the format codeword nearest to either copy.
+fn read_format(dark: &impl Fn(u32, u32) -> bool, size: u32) -> Result<(ErrorLevel, Mask), Error> {
+    let (data, _) = format_positions(size)
+        .iter()
+        .filter_map(|copy| {
+            let received = copy
+                .iter()
+                .fold(0, |bits, (x, y)| bits << 1 | dark(*x, *y) as u32);
+            bch::decode_format(received)
+        })
+        .min_by_key(|(_, errors)| *errors)
+        .ok_or(Error::InvalidImage("format information unreadable"))?;
+    let error_level = *ERROR_LEVELS
+        .iter()
+        .find(|level| LEVEL_INDICATOR[**level as usize] as u32 == data >> 3)
+        .unwrap();
+    Ok((error_level, MASKS[(data & 7) as usize]))
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+    fn read(&mut self, count: usize) -> Result<u32, Error> {
+        if count > self.remaining() {
+            return Err(Error::InvalidImage("segment runs past the data codewords"));
+        }
+        let mut value = 0;
+        for _ in 0..count {
+            let bit = self.bytes[self.position / 8] >> (7 - self.position % 8) & 1;
+            value = value << 1 | bit as u32;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// Bytes of the numeric, alphanumeric, byte and Kanji (as Shift JIS)
+/// segments up to the terminator.
+fn read_segments(data: &[u8], version: u8) -> Result<Vec<u8>, Error> {
+    let mut reader = BitReader {
+        bytes: data,
+        position: 0,
+    };
+    let mut bytes: Vec<u8> = Vec::new();
+    while reader.remaining() >= 4 {
+        let mode = match reader.read(4)? {
+            0 => break,
+            1 => ByteNameOfMode::Numeric,
+            2 => ByteNameOfMode::Alpha,
+            4 => ByteNameOfMode::Byte,
+            8 => ByteNameOfMode::Kanji,
+            _ => {
+                return Err(Error::Unsupported(
+                    "ECI, FNC1 and structured append segments",
+                ))
+            }
+        };
+        let mut count = reader.read(utils::get_len_bit(&mode, version) as usize)?;
+        match mode {
+            ByteNameOfMode::Numeric => {
+                while count > 0 {
+                    let digits = count.min(3);
+                    let value = reader.read([0, 4, 7, 10][digits as usize])?;
+                    if value >= [1, 10, 100, 1000][digits as usize] {
+                        return Err(Error::InvalidImage("numeric group out of range"));
+                    }
+                    let text = format!("{:0width$}", value, width = digits as usize);
+                    bytes.extend(text.bytes());
+                    count -= digits;
+                }
+            }
+            ByteNameOfMode::Alpha => {
+                while count > 0 {
+                    let (bits, limit) = if count == 1 { (6, 45) } else { (11, 45 * 45) };
+                    let value = reader.read(bits)? as usize;
+                    if value >= limit {
+                        return Err(Error::InvalidImage("alphanumeric value out of range"));
+                    }
+                    if count == 1 {
+                        bytes.push(ALPHANUMERIC[value]);
+                        break;
+                    }
+                    bytes.extend([ALPHANUMERIC[value / 45], ALPHANUMERIC[value % 45]]);
+                    count -= 2;
+                }
+            }
+            ByteNameOfMode::Byte => {
+                for _ in 0..count {
+                    bytes.push(reader.read(8)? as u8);
+                }
+            }
+            ByteNameOfMode::Kanji => {
+                for _ in 0..count {
+                    let value = reader.read(13)?;
+                    let code = ((value / 0xc0) << 8) | (value % 0xc0);
+                    let code = code + if code < 0x1f00 { 0x8140 } else { 0xc140 };
+                    bytes.extend([(code >> 8) as u8, code as u8]);
+                }
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// Reads a symbol from its modules (`0` dark, anything else light, no
+/// quiet zone), correcting each block with its Reed-Solomon codewords.
+pub fn decode(modules: &Matrix<u8>) -> Result<Decoded, Error> {
+    let (size, height) = modules.size();
+    if size != height || !(21..=177).contains(&size) || size % 4 != 1 {
+        return Err(Error::InvalidImage("not a QR code symbol size"));
+    }
+    let version = ((size - 17) / 4) as u8;
+    let dark = |x: u32, y: u32| modules.get((x, y)) == 0;
+    let (error_level, mask) = read_format(&dark, size)?;
+    // A symbol of the same version, level and mask gives the module of
+    // every bit of the codeword stream.
+    let mut layout = QRcode::from_reed_solomon(ReedSolomonData::new(
+        &[],
+        error_level,
+        version,
+        mask,
+        ByteNameOfMode::Byte,
+    ));
+    layout.render();
+    let stream_bits = layout.rs.bits.len() - REMINDER[(version - 1) as usize];
+    let stream: Vec<u8> = layout.placement()[..stream_bits]
+        .chunks(8)
+        .map(|points| {
+            points.iter().fold(0, |codeword, (x, y)| {
+                let bit = dark(*x, *y) != utils::mask(mask, *x as u16, *y as u16);
+                codeword << 1 | bit as u8
+            })
+        })
+        .collect();
+    let (data_codewords, blocks, groups, codewords_in_group, error_correction) =
+        utils::get_error_correction_level_data(version, &error_level);
+    let lengths: Vec<usize> = (0..blocks as usize)
+        .map(|block| codewords_in_group[(block >= groups[0] as usize) as usize] as usize)
+        .collect();
+    let mut codewords: Vec<Vec<u32>> = lengths
+        .iter()
+        .map(|length| Vec::with_capacity(*length))
+        .collect();
+    let mut next = stream.iter();
+    for i in 0..lengths[lengths.len() - 1] {
+        for (block, length) in codewords.iter_mut().zip(&lengths) {
+            if i < *length {
+                block.push(*next.next().unwrap() as u32);
+            }
+        }
+    }
+    for _ in 0..error_correction {
+        for block in codewords.iter_mut() {
+            block.push(*next.next().unwrap() as u32);
+        }
+    }
+    let mut data: Vec<u8> = Vec::with_capacity(data_codewords as usize);
+    let mut corrected = 0;
+    for (block, length) in codewords.iter_mut().zip(&lengths) {
+        corrected += qr_galois_field()
+            .correct(block, error_correction as usize)
+            .ok_or(Error::InvalidImage("too many errors to correct"))?;
+        data.extend(block[..*length].iter().map(|codeword| *codeword as u8));
+    }
+    let bytes = read_segments(&data, version)?;
+    Ok(Decoded {
+        version,
+        error_level,
+        mask,
+        text: String::from_utf8(bytes.clone()).ok(),
+        bytes,
+        corrected,
+    })
+}
+
+/// Locates, samples and decodes a symbol in a grayscale image, pixels
+/// below `threshold` being dark.
+pub fn decode_image(image: &Matrix<u8>, threshold: u8) -> Result<Decoded, Error> {
+    let location = locate::locate(image, threshold)?;
+    decode(&sample::sample(image, threshold, &location)?)
+}
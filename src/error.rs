@@ -0,0 +1,30 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The payload does not fit in the largest symbol of the selected kind.
+    DataTooLong,
+    /// An option is outside the range the symbology allows.
+    InvalidOption(&'static str),
+    /// The request is valid but this crate cannot produce it.
+    Unsupported(&'static str),
+    /// Image data that could not be decoded.
+    InvalidImage(&'static str),
+    /// Reading the payload failed.
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::DataTooLong => write!(f, "data too long for the largest symbol"),
+            Error::InvalidOption(reason) => write!(f, "invalid option: {}", reason),
+            Error::Unsupported(reason) => write!(f, "unsupported: {}", reason),
+            Error::InvalidImage(reason) => write!(f, "invalid image: {}", reason),
+            Error::Io(kind) => write!(f, "read failed: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
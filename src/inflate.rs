@@ -0,0 +1,305 @@
+use crate::bitmap::adler32;
+use crate::error::Error;
+
+/// Base length and extra bits of length symbols 257 to 285.
+const LENGTHS: [(u16, u8); 29] = [
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 1),
+    (13, 1),
+    (15, 1),
+    (17, 1),
+    (19, 2),
+    (23, 2),
+    (27, 2),
+    (31, 2),
+    (35, 3),
+    (43, 3),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 4),
+    (115, 4),
+    (131, 5),
+    (163, 5),
+    (195, 5),
+    (227, 5),
+    (258, 0),
+];
+
+/// Base distance and extra bits of distance symbols 0 to 29.
+const DISTANCES: [(u16, u8); 30] = [
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 1),
+    (7, 1),
+    (9, 2),
+    (13, 2),
+    (17, 3),
+    (25, 3),
+    (33, 4),
+    (49, 4),
+    (65, 5),
+    (97, 5),
+    (129, 6),
+    (193, 6),
+    (257, 7),
+    (385, 7),
+    (513, 8),
+    (769, 8),
+    (1025, 9),
+    (1537, 9),
+    (2049, 10),
+    (3073, 10),
+    (4097, 11),
+    (6145, 11),
+    (8193, 12),
+    (12289, 12),
+    (16385, 13),
+    (24577, 13),
+];
+
+/// Order in which a dynamic block lists the code lengths of the code
+/// length alphabet.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Deflate reads bits from the least significant end of each byte.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn bit(&mut self) -> Result<u32, Error> {
+        let byte = self
+            .data
+            .get(self.position / 8)
+            .ok_or(Error::InvalidImage("deflate stream ends early"))?;
+        let bit = (byte >> (self.position % 8)) & 1;
+        self.position += 1;
+        Ok(bit as u32)
+    }
+    fn bits(&mut self, count: u8) -> Result<u32, Error> {
+        let mut value = 0;
+        for i in 0..count {
+            value |= self.bit()? << i;
+        }
+        Ok(value)
+    }
+    fn align(&mut self) {
+        self.position = self.position.div_ceil(8) * 8;
+    }
+}
+
+/// Canonical Huffman code: how many codes of each length, and the symbols
+/// in code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, Error> {
+        let mut counts = [0u16; 16];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut left: i32 = 1;
+        for count in &counts[1..] {
+            left = left * 2 - *count as i32;
+            if left < 0 {
+                return Err(Error::InvalidImage("oversubscribed Huffman code"));
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length != 0 {
+                symbols[offsets[*length as usize] as usize] = symbol as u16;
+                offsets[*length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+    /// Reads one code a bit at a time, the first code of each length
+    /// following the last one of the length before.
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, Error> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for count in &self.counts[1..] {
+            code |= reader.bit()? as i32;
+            let count = *count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(Error::InvalidImage("invalid Huffman code"))
+    }
+}
+
+/// Literal/length and distance codes of a block with fixed Huffman codes.
+fn fixed() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    for (symbol, length) in lengths.iter_mut().enumerate() {
+        *length = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    (
+        Huffman::new(&lengths).unwrap(),
+        Huffman::new(&[5; 30]).unwrap(),
+    )
+}
+
+/// Literal/length and distance codes described at the start of a block
+/// with dynamic Huffman codes.
+fn dynamic(reader: &mut BitReader) -> Result<(Huffman, Huffman), Error> {
+    let literals = reader.bits(5)? as usize + 257;
+    let distances = reader.bits(5)? as usize + 1;
+    let code_lengths = reader.bits(4)? as usize + 4;
+    let mut lengths = [0u8; 19];
+    for index in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[*index] = reader.bits(3)? as u8;
+    }
+    let code = Huffman::new(&lengths)?;
+    let mut lengths: Vec<u8> = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (value, repeat) = match code.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or(Error::InvalidImage("code length repeat without a length"))?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend((0..repeat).map(|_| value));
+    }
+    if lengths.len() > literals + distances {
+        return Err(Error::InvalidImage("code lengths overrun the alphabets"));
+    }
+    if lengths[256] == 0 {
+        return Err(Error::InvalidImage("no code for the end of block"));
+    }
+    Ok((
+        Huffman::new(&lengths[..literals])?,
+        Huffman::new(&lengths[literals..])?,
+    ))
+}
+
+/// Decompresses a raw deflate stream: stored, fixed Huffman and dynamic
+/// Huffman blocks. Output beyond `max_length` bytes is an error.
+pub fn inflate(data: &[u8], max_length: usize) -> Result<Vec<u8>, Error> {
+    const TOO_LONG: Error = Error::InvalidImage("deflate output longer than expected");
+    let mut reader = BitReader { data, position: 0 };
+    let mut output: Vec<u8> = Vec::new();
+    loop {
+        let last = reader.bit()? == 1;
+        let (literal, distance) = match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let start = reader.position / 8;
+                let header = data
+                    .get(start..start + 4)
+                    .ok_or(Error::InvalidImage("deflate stream ends early"))?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err(Error::InvalidImage("stored block length mismatch"));
+                }
+                let block = data
+                    .get(start + 4..start + 4 + length as usize)
+                    .ok_or(Error::InvalidImage("deflate stream ends early"))?;
+                if output.len() + block.len() > max_length {
+                    return Err(TOO_LONG);
+                }
+                output.extend_from_slice(block);
+                reader.position = (start + 4 + length as usize) * 8;
+                if last {
+                    return Ok(output);
+                }
+                continue;
+            }
+            1 => fixed(),
+            2 => dynamic(&mut reader)?,
+            _ => return Err(Error::InvalidImage("reserved deflate block type")),
+        };
+        loop {
+            let symbol = literal.decode(&mut reader)? as usize;
+            if symbol < 256 {
+                if output.len() == max_length {
+                    return Err(TOO_LONG);
+                }
+                output.push(symbol as u8);
+                continue;
+            }
+            if symbol == 256 {
+                break;
+            }
+            let (base, extra) = *LENGTHS
+                .get(symbol - 257)
+                .ok_or(Error::InvalidImage("invalid length symbol"))?;
+            let length = base as usize + reader.bits(extra)? as usize;
+            let (base, extra) = *DISTANCES
+                .get(distance.decode(&mut reader)? as usize)
+                .ok_or(Error::InvalidImage("invalid distance symbol"))?;
+            let distance = base as usize + reader.bits(extra)? as usize;
+            if distance > output.len() {
+                return Err(Error::InvalidImage("distance before the start of output"));
+            }
+            if output.len() + length > max_length {
+                return Err(TOO_LONG);
+            }
+            // Copies byte by byte: a match may overlap the bytes it produces.
+            let start = output.len() - distance;
+            for i in 0..length {
+                output.push(output[start + i]);
+            }
+        }
+        if last {
+            return Ok(output);
+        }
+    }
+}
+
+/// Decompresses a zlib stream of at most `max_length` bytes, checking its
+/// header and Adler-32 checksum.
+pub fn zlib_decompress(data: &[u8], max_length: usize) -> Result<Vec<u8>, Error> {
+    if data.len() < 6 {
+        return Err(Error::InvalidImage("zlib stream too short"));
+    }
+    if data[0] & 0x0f != 8 || !u16::from_be_bytes([data[0], data[1]]).is_multiple_of(31) {
+        return Err(Error::InvalidImage("invalid zlib header"));
+    }
+    if data[1] & 0x20 != 0 {
+        return Err(Error::Unsupported("zlib preset dictionaries"));
+    }
+    let output = inflate(&data[2..data.len() - 4], max_length)?;
+    let end = data.len();
+    let checksum = u32::from_be_bytes([data[end - 4], data[end - 3], data[end - 2], data[end - 1]]);
+    if adler32(&output) != checksum {
+        return Err(Error::InvalidImage("zlib checksum mismatch"));
+    }
+    Ok(output)
+}
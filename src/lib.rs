@@ -0,0 +1,27 @@
+pub mod aztec;
+pub mod bch;
+pub mod binarize;
+pub mod bitmap;
+pub mod capacity;
+pub mod color;
+pub mod config;
+pub mod contour;
+pub mod decode;
+pub mod error;
+pub mod inflate;
+pub mod locate;
+pub mod logo;
+pub mod netpbm;
+pub mod pdf417;
+pub mod placement;
+pub mod png;
+pub mod polynomial;
+pub mod print;
+pub mod qr_code;
+pub mod reed_solomon;
+pub mod render;
+pub mod report;
+pub mod sample;
+pub mod style;
+pub mod trace;
+pub mod utils;
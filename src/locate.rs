@@ -0,0 +1,306 @@
+use crate::error::Error;
+use crate::qr_code::Matrix;
+
+/// Candidate finder pattern center in image pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FinderPattern {
+    pub x: f32,
+    pub y: f32,
+    pub module_size: f32,
+    /// Scan lines that confirmed the pattern.
+    pub count: u32,
+}
+
+/// Three finder patterns of one symbol and what follows from them.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Location {
+    pub top_left: FinderPattern,
+    pub top_right: FinderPattern,
+    pub bottom_left: FinderPattern,
+    pub module_size: f32,
+    /// Angle of the top edge, degrees clockwise from the image x axis.
+    pub rotation: f32,
+    /// Modules per side, `4 * version + 17`.
+    pub dimension: u32,
+    pub version: u8,
+    /// Outer corners of the symbol, clockwise from the top left, assuming
+    /// no perspective distortion.
+    pub corners: [(f32, f32); 4],
+}
+
+/// Module size of five runs in a 1:1:3:1:1 ratio, each within half a
+/// module.
+fn finder_ratio(counts: &[u32; 5]) -> Option<f32> {
+    let total: u32 = counts.iter().sum();
+    if total < 7 {
+        return None;
+    }
+    let module = total as f32 / 7.0;
+    let tolerance = module / 2.0;
+    let fits = counts.iter().enumerate().all(|(i, count)| {
+        let (expected, tolerance) = if i == 2 {
+            (module * 3.0, tolerance * 3.0)
+        } else {
+            (module, tolerance)
+        };
+        (*count as f32 - expected).abs() < tolerance
+    });
+    if fits {
+        Some(module)
+    } else {
+        None
+    }
+}
+
+/// Center and module size of a finder pattern crossing `line` at `start`,
+/// the line being `length` pixels long.
+fn cross_check(line: &impl Fn(i64) -> bool, start: i64, length: i64) -> Option<(f32, f32)> {
+    let mut counts = [0; 5];
+    let mut i = start;
+    for (run, dark) in [(2, true), (1, false), (0, true)] {
+        while i >= 0 && line(i) == dark {
+            counts[run] += 1;
+            i -= 1;
+        }
+    }
+    let mut i = start + 1;
+    for (run, dark) in [(2, true), (3, false), (4, true)] {
+        while i < length && line(i) == dark {
+            counts[run] += 1;
+            i += 1;
+        }
+    }
+    let module = finder_ratio(&counts)?;
+    let center = i as f32 - counts[4] as f32 - counts[3] as f32 - counts[2] as f32 / 2.0;
+    Some((center, module))
+}
+
+/// Finder pattern candidates of every row, each confirmed by a vertical
+/// and a second horizontal scan through its center, merged when they
+/// describe the same pattern; most confirmed first.
+pub fn find_finders(image: &Matrix<u8>, threshold: u8) -> Vec<FinderPattern> {
+    let (width, height) = image.size();
+    let dark = |x: i64, y: i64| image.get((x as u32, y as u32)) < threshold;
+    let mut finders: Vec<FinderPattern> = Vec::new();
+    for y in 0..height as i64 {
+        let mut runs: Vec<(bool, u32)> = Vec::new();
+        for x in 0..width as i64 {
+            match runs.last_mut() {
+                Some((color, length)) if *color == dark(x, y) => *length += 1,
+                _ => runs.push((dark(x, y), 1)),
+            }
+        }
+        let mut end = 0;
+        for (i, (color, length)) in runs.iter().enumerate() {
+            end += length;
+            if i < 4 || !*color {
+                continue;
+            }
+            let counts = [
+                runs[i - 4].1,
+                runs[i - 3].1,
+                runs[i - 2].1,
+                runs[i - 1].1,
+                *length,
+            ];
+            if !runs[i - 4].0 || finder_ratio(&counts).is_none() {
+                continue;
+            }
+            let x = end as f32 - (counts[4] + counts[3]) as f32 - counts[2] as f32 / 2.0;
+            let Some((center_y, vertical)) = cross_check(&|i| dark(x as i64, i), y, height as i64)
+            else {
+                continue;
+            };
+            let Some((center_x, horizontal)) =
+                cross_check(&|i| dark(i, center_y as i64), x as i64, width as i64)
+            else {
+                continue;
+            };
+            let module_size = (vertical + horizontal) / 2.0;
+            match finders.iter_mut().find(|finder| {
+                (finder.x - center_x).abs() <= finder.module_size
+                    && (finder.y - center_y).abs() <= finder.module_size
+                    && (finder.module_size - module_size).abs() <= finder.module_size
+            }) {
+                Some(finder) => {
+                    let count = finder.count as f32;
+                    finder.x = (finder.x * count + center_x) / (count + 1.0);
+                    finder.y = (finder.y * count + center_y) / (count + 1.0);
+                    finder.module_size = (finder.module_size * count + module_size) / (count + 1.0);
+                    finder.count += 1;
+                }
+                None => finders.push(FinderPattern {
+                    x: center_x,
+                    y: center_y,
+                    module_size,
+                    count: 1,
+                }),
+            }
+        }
+    }
+    finders.sort_by_key(|finder| std::cmp::Reverse(finder.count));
+    finders
+}
+
+fn distance(a: &FinderPattern, b: &FinderPattern) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+/// Width of the pattern at `from` along the line to `toward`: from its
+/// center both ways through dark, light and dark to the surrounding light.
+/// Unlike scan line runs this is 7 modules whatever the rotation; the
+/// median of five parallel lines through the center square evens out
+/// jagged edges.
+fn pattern_width(
+    dark: &impl Fn(f32, f32) -> bool,
+    from: &FinderPattern,
+    toward: &FinderPattern,
+) -> f32 {
+    let length = distance(from, toward);
+    let direction = ((toward.x - from.x) / length, (toward.y - from.y) / length);
+    let limit = (from.module_size * 8.0) as u32;
+    let walk = |start: (f32, f32), sign: f32| {
+        let mut expected = true;
+        let mut switches = 0;
+        let mut step = 0;
+        while step < limit {
+            let x = start.0 + direction.0 * sign * step as f32;
+            let y = start.1 + direction.1 * sign * step as f32;
+            if dark(x, y) != expected {
+                switches += 1;
+                if switches == 3 {
+                    break;
+                }
+                expected = !expected;
+            }
+            step += 1;
+        }
+        // The edge lies between the last step inside and the first outside.
+        step as f32 - 0.5
+    };
+    let mut widths: Vec<f32> = [-1.0, -0.5, 0.0, 0.5, 1.0]
+        .iter()
+        .map(|offset| {
+            let shift = offset * from.module_size;
+            let start = (from.x - direction.1 * shift, from.y + direction.0 * shift);
+            walk(start, 1.0) + walk(start, -1.0)
+        })
+        .collect();
+    widths.sort_by(|a, b| a.total_cmp(b));
+    widths[2]
+}
+
+/// How far three patterns are from the corners of a right isosceles
+/// triangle with equal module sizes; `0.0` is a perfect match.
+fn triangle_error(patterns: [&FinderPattern; 3]) -> f32 {
+    let mut sides = [
+        distance(patterns[0], patterns[1]),
+        distance(patterns[0], patterns[2]),
+        distance(patterns[1], patterns[2]),
+    ];
+    sides.sort_by(|a, b| a.total_cmp(b));
+    let [a, b, c] = sides;
+    let sizes = patterns.map(|pattern| pattern.module_size);
+    let largest = sizes.iter().copied().fold(0.0, f32::max);
+    let smallest = sizes.iter().copied().fold(f32::MAX, f32::min);
+    (b - a) / b + (c - (a * a + b * b).sqrt()).abs() / c + (largest - smallest) / largest
+}
+
+/// Finds the three finder patterns of a symbol in a grayscale image,
+/// pixels below `threshold` being dark, and derives orientation, module
+/// size, version and corners from them.
+pub fn locate(image: &Matrix<u8>, threshold: u8) -> Result<Location, Error> {
+    let finders = find_finders(image, threshold);
+    let candidates = &finders[..finders.len().min(12)];
+    let mut best: Option<(f32, [&FinderPattern; 3])> = None;
+    for i in 0..candidates.len() {
+        for j in i + 1..candidates.len() {
+            for k in j + 1..candidates.len() {
+                let patterns = [&candidates[i], &candidates[j], &candidates[k]];
+                let error = triangle_error(patterns);
+                if best.is_none_or(|(lowest, _)| error < lowest) {
+                    best = Some((error, patterns));
+                }
+            }
+        }
+    }
+    let (_, [a, b, c]) = best.ok_or(Error::InvalidImage("no three finder patterns found"))?;
+    // The top left pattern faces the longest side.
+    let (top_left, first, second) = if distance(b, c) >= distance(a, b).max(distance(a, c)) {
+        (a, b, c)
+    } else if distance(a, c) >= distance(a, b) {
+        (b, a, c)
+    } else {
+        (c, a, b)
+    };
+    // With y pointing down, top right to bottom left turns clockwise.
+    let cross = (first.x - top_left.x) * (second.y - top_left.y)
+        - (first.y - top_left.y) * (second.x - top_left.x);
+    let (top_right, bottom_left) = if cross > 0.0 {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    let dark = |x: f32, y: f32| {
+        let (width, height) = image.size();
+        x >= 0.0
+            && y >= 0.0
+            && (x as u32) < width
+            && (y as u32) < height
+            && image.get((x as u32, y as u32)) < threshold
+    };
+    let module_size = [
+        (top_left, top_right),
+        (top_left, bottom_left),
+        (top_right, top_left),
+        (bottom_left, top_left),
+    ]
+    .iter()
+    .map(|(from, toward)| pattern_width(&dark, from, toward) / 7.0)
+    .sum::<f32>()
+        / 4.0;
+    let across = (distance(top_left, top_right) + distance(top_left, bottom_left)) / 2.0;
+    let mut dimension = (across / module_size).round() as u32 + 7;
+    match dimension % 4 {
+        0 => dimension += 1,
+        2 => dimension -= 1,
+        3 => dimension -= 2,
+        _ => {}
+    }
+    let dimension = dimension.clamp(21, 177);
+    let span = (dimension - 7) as f32;
+    let right = (
+        (top_right.x - top_left.x) / span,
+        (top_right.y - top_left.y) / span,
+    );
+    let down = (
+        (bottom_left.x - top_left.x) / span,
+        (bottom_left.y - top_left.y) / span,
+    );
+    let corner = |from: &FinderPattern, along: f32, across: f32| {
+        (
+            from.x + right.0 * along + down.0 * across,
+            from.y + right.1 * along + down.1 * across,
+        )
+    };
+    let bottom_right = FinderPattern {
+        x: top_right.x + bottom_left.x - top_left.x,
+        y: top_right.y + bottom_left.y - top_left.y,
+        ..*top_left
+    };
+    Ok(Location {
+        top_left: *top_left,
+        top_right: *top_right,
+        bottom_left: *bottom_left,
+        module_size,
+        rotation: right.1.atan2(right.0).to_degrees(),
+        dimension,
+        version: ((dimension - 17) / 4) as u8,
+        corners: [
+            corner(top_left, -3.5, -3.5),
+            corner(top_right, 3.5, -3.5),
+            corner(&bottom_right, 3.5, 3.5),
+            corner(bottom_left, -3.5, 3.5),
+        ],
+    })
+}
@@ -0,0 +1,198 @@
+use crate::bitmap::{base64, Bitmap, RgbaImage};
+use crate::config::{ErrorLevel, ModuleKind};
+use crate::error::Error;
+use crate::qr_code::{QRcode, QRcodeOptions};
+use crate::render::{self, Symbol};
+
+/// Centered logo area, `width` and `height` as fractions of the symbol side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Logo {
+    pub width: f32,
+    pub height: f32,
+    /// Turn the data modules under the logo light instead of leaving them.
+    pub clear: bool,
+    /// Share of each block's correctable codewords (half its error
+    /// correction codewords) the area may destroy, `0.0` to `1.0`.
+    pub safety: f32,
+}
+
+impl Default for Logo {
+    fn default() -> Self {
+        Logo {
+            width: 0.2,
+            height: 0.2,
+            clear: true,
+            safety: 0.6,
+        }
+    }
+}
+
+/// Where a logo went, in symbol modules, and the
+/// codewords it destroys and could be corrected, per block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogoArea {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub damaged: Vec<u16>,
+    pub correctable: Vec<u16>,
+}
+
+pub enum LogoImage {
+    Svg(String),
+    Png(Vec<u8>),
+}
+
+/// Modules covering `fraction` of `size`, with the same parity as `size`
+/// so the area stays centered on whole modules. A fraction of `0.0` covers
+/// nothing.
+fn centered(size: u32, fraction: f32) -> (u32, u32) {
+    let mut length = ((size as f32 * fraction).ceil() as u32).min(size);
+    if length > 0 && (size - length) % 2 == 1 {
+        length += 1;
+    }
+    ((size - length) / 2, length)
+}
+
+/// Reserves the logo area on a rendered symbol. Refused when the area
+/// covers finder, timing, format or version modules, or destroys more
+/// codewords in any block than `logo.safety` of what the block corrects.
+/// Alignment patterns are kept, the center one of larger versions
+/// included. A width or height of `0.0` places no logo and leaves every
+/// module as it is.
+pub fn place(qrcode: &mut QRcode, logo: &Logo) -> Result<LogoArea, Error> {
+    if !(0.0..=1.0).contains(&logo.width) || !(0.0..=1.0).contains(&logo.height) {
+        return Err(Error::InvalidOption(
+            "logo size is a fraction of the symbol",
+        ));
+    }
+    if !(0.0..=1.0).contains(&logo.safety) {
+        return Err(Error::InvalidOption("logo safety is 0.0..1.0"));
+    }
+    let size = qrcode.size as u32;
+    let (mut x, mut width) = centered(size, logo.width);
+    let (mut y, mut height) = centered(size, logo.height);
+    if width == 0 || height == 0 {
+        (x, width, y, height) = (size / 2, 0, size / 2, 0);
+    }
+    let (_, blocks, _, _, error_correction) = qrcode.rs.error_correction_data();
+    let mut codewords: Vec<usize> = Vec::new();
+    let mut cleared: Vec<(u32, u32)> = Vec::new();
+    for row in y..y + height {
+        for column in x..x + width {
+            let point = (column, row);
+            match qrcode.module_kinds().get(point) {
+                ModuleKind::Data { codeword, .. }
+                | ModuleKind::ErrorCorrection { codeword, .. } => {
+                    codewords.push(codeword as usize);
+                    cleared.push(point);
+                }
+                ModuleKind::Remainder => cleared.push(point),
+                ModuleKind::Alignment => {}
+                _ => return Err(Error::InvalidOption("logo area covers function patterns")),
+            }
+        }
+    }
+    codewords.sort();
+    codewords.dedup();
+    let mut damaged = vec![0; blocks as usize];
+    for codeword in codewords {
+        damaged[qrcode.rs.codeword_block(codeword)] += 1;
+    }
+    let correctable = vec![error_correction / 2; blocks as usize];
+    if damaged
+        .iter()
+        .any(|damaged| *damaged as f32 > logo.safety * (error_correction / 2) as f32)
+    {
+        return Err(Error::InvalidOption(
+            "logo area destroys too many codewords of a block",
+        ));
+    }
+    if logo.clear {
+        for point in cleared {
+            qrcode.put_module(point, 1);
+        }
+    }
+    Ok(LogoArea {
+        x,
+        y,
+        width,
+        height,
+        damaged,
+        correctable,
+    })
+}
+
+/// Rendered symbol at error level H with a logo area placed.
+pub fn with_logo(
+    data: &str,
+    options: QRcodeOptions,
+    logo: &Logo,
+) -> Result<(QRcode, LogoArea), Error> {
+    let options = QRcodeOptions {
+        error_level: ErrorLevel::H,
+        ..options
+    };
+    let mut qrcode = QRcode::with_options(data, options)?;
+    qrcode.render();
+    let area = place(&mut qrcode, logo)?;
+    Ok((qrcode, area))
+}
+
+/// `render::to_svg` with the logo embedded as a data URI image over its
+/// area; nothing is embedded for an empty area.
+pub fn to_svg(qrcode: &QRcode, area: &LogoArea, image: &LogoImage, module_size: u32) -> String {
+    let svg = render::to_svg(qrcode, module_size);
+    if area.width == 0 || area.height == 0 {
+        return svg;
+    }
+    let (mime, data) = match image {
+        LogoImage::Svg(svg) => ("image/svg+xml", base64(svg.as_bytes())),
+        LogoImage::Png(png) => ("image/png", base64(png)),
+    };
+    let quiet_zone = qrcode.quiet_zone();
+    let image = format!(
+        "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" href=\"data:{};base64,{}\"/>",
+        (area.x + quiet_zone) * module_size,
+        (area.y + quiet_zone) * module_size,
+        area.width * module_size,
+        area.height * module_size,
+        mime,
+        data
+    );
+    svg.replace("</svg>", &(image + "</svg>"))
+}
+
+/// Raster of the symbol with `scale` pixels per module and the logo drawn
+/// over its area, resized nearest-neighbour and blended by its alpha. An
+/// empty logo draws nothing.
+pub fn to_rgba(qrcode: &QRcode, area: &LogoArea, logo: &RgbaImage, scale: u32) -> RgbaImage {
+    let bitmap = Bitmap::from_symbol(qrcode, scale);
+    let mut image = RgbaImage {
+        width: bitmap.width,
+        height: bitmap.height,
+        pixels: bitmap
+            .pixels
+            .iter()
+            .flat_map(|gray| [*gray, *gray, *gray, 255])
+            .collect(),
+    };
+    if logo.width == 0 || logo.height == 0 {
+        return image;
+    }
+    let quiet_zone = qrcode.quiet_zone();
+    let (left, top) = ((area.x + quiet_zone) * scale, (area.y + quiet_zone) * scale);
+    let (width, height) = (area.width * scale, area.height * scale);
+    for y in 0..height {
+        for x in 0..width {
+            let source = logo.get(x * logo.width / width, y * logo.height / height);
+            let index = (((top + y) * image.width + left + x) * 4) as usize;
+            let alpha = source[3] as u32;
+            for (below, channel) in image.pixels[index..index + 3].iter_mut().zip(source) {
+                *below = ((channel as u32 * alpha + *below as u32 * (255 - alpha)) / 255) as u8;
+            }
+        }
+    }
+    image
+}
@@ -1,19 +1,45 @@
-mod config;
-mod polynomial;
-mod qr_code;
-mod reed_solomon;
-mod utils;
-use crate::qr_code::QRcode;
+use qrcode::config;
+use qrcode::qr_code::QRcode;
+use qrcode::render;
+use std::process;
+
+const USAGE: &str =
+    "usage: qrcode [--format text|inverted|ansi|ascii|sixel|kitty|iterm] [--scale N] [DATA]";
 
 fn main() {
-    let mut qrcode: QRcode = QRcode::new(
-        "https://youtu.be/dQw4w9WgXcQ",
-        1,
-        config::ErrorLevel::H,
-        config::Mask::_100,
-    );
+    let mut format = "text".to_string();
+    let mut scale: u32 = 4;
+    let mut data = "https://youtu.be/dQw4w9WgXcQ".to_string();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next().unwrap_or_default(),
+            "--scale" => match args.next().and_then(|value| value.parse().ok()) {
+                Some(value) if value > 0 => scale = value,
+                _ => {
+                    eprintln!("{}", USAGE);
+                    process::exit(2);
+                }
+            },
+            _ => data = arg,
+        }
+    }
+    let mut qrcode: QRcode = QRcode::new(&data, 1, config::ErrorLevel::H, config::Mask::_100);
     qrcode.render();
-    println!("{:?}", qrcode);
+    let output = match format.as_str() {
+        "text" => qrcode.to_string(),
+        "inverted" => render::to_half_blocks(&qrcode, true),
+        "ansi" => render::to_ansi(&qrcode),
+        "ascii" => render::to_ascii(&qrcode),
+        "sixel" => render::to_sixel(&qrcode, scale),
+        "kitty" => render::to_kitty(&qrcode, scale),
+        "iterm" => render::to_iterm(&qrcode, scale),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    println!("{}", output);
     println!("{:?}", qrcode.rs.version);
     println!("{:?}", qrcode.rs.error_level);
 }
@@ -28,3 +54,1259 @@ fn test_qrcode_version() {
     );
     assert_eq!(qrcode.rs.version, 4);
 }
+#[test]
+fn test_reed_solomon_remainder() {
+    let field = qrcode::polynomial::qr_galois_field();
+    let data = [
+        32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+    ];
+    assert_eq!(
+        field.remainder(&data, 10),
+        vec![196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+    );
+}
+#[test]
+fn test_aztec_layers() {
+    let aztec = qrcode::aztec::AztecCode::new("This is an example Aztec symbol for Wikipedia.", 33)
+        .unwrap();
+    assert!(aztec.compact);
+    assert_eq!(aztec.layers, 3);
+    assert_eq!(aztec.size, 23);
+    let empty = qrcode::aztec::AztecCode::new("", 23).unwrap();
+    assert!(empty.compact);
+    assert_eq!((empty.layers, empty.data_words, empty.size), (1, 1, 15));
+}
+#[test]
+fn test_pdf417_compaction() {
+    use qrcode::pdf417;
+    assert_eq!(
+        pdf417::encode_numeric(b"000213298174000"),
+        vec![1, 624, 434, 632, 282, 200]
+    );
+    let (text, _) = pdf417::encode_text(b"PDF417", pdf417::TextSubmode::Alpha);
+    assert_eq!(text, vec![453, 178, 121, 239]);
+    let symbol = pdf417::PDF417::new("PDF417 symbol", Some(2), None).unwrap();
+    let field = pdf417::pdf417_field();
+    for i in 1..=8 {
+        let syndrome = symbol.codewords.iter().fold(0, |acc, codeword| {
+            field.add(field.multiply(acc, field.exp(i)), *codeword)
+        });
+        assert_eq!(syndrome, 0);
+    }
+}
+
+#[test]
+fn test_pdf417_cluster_patterns() {
+    use qrcode::pdf417::{self, CLUSTER_PATTERNS};
+    use qrcode::render::Symbol;
+    let widths = |pattern: u32| {
+        let bits = format!("{:017b}", pattern);
+        let mut widths = Vec::new();
+        let mut previous = ' ';
+        for bit in bits.chars() {
+            if bit == previous {
+                *widths.last_mut().unwrap() += 1;
+            } else {
+                widths.push(1);
+                previous = bit;
+            }
+        }
+        widths
+    };
+    // Codeword 0 and 928 of each cluster from ISO/IEC 15438 Annex A.
+    assert_eq!(widths(CLUSTER_PATTERNS[0][0]), [3, 1, 1, 1, 1, 1, 3, 6]);
+    assert_eq!(widths(CLUSTER_PATTERNS[1][0]), [5, 1, 1, 1, 1, 1, 2, 5]);
+    assert_eq!(widths(CLUSTER_PATTERNS[2][0]), [2, 1, 1, 1, 1, 1, 5, 5]);
+    assert_eq!(
+        [
+            CLUSTER_PATTERNS[0][928],
+            CLUSTER_PATTERNS[1][928],
+            CLUSTER_PATTERNS[2][928]
+        ],
+        [0x1bef4, 0x13f26, 0x1c7ea]
+    );
+    assert_eq!(pdf417::PDF417_START_PATTERN, [8, 1, 1, 1, 1, 1, 1, 3]);
+    assert_eq!(pdf417::PDF417_STOP_PATTERN, [7, 1, 1, 3, 1, 1, 1, 2, 1]);
+    // Every row reads back as start, left indicator, data, right indicator
+    // and stop through the cluster of that row.
+    let symbol = pdf417::PDF417::new("PDF417 symbol", Some(2), None).unwrap();
+    let modules = symbol.modules();
+    for row in 0..symbol.rows as usize {
+        let y = row as u32 * pdf417::PDF417_ROW_HEIGHT;
+        let read = |start: u32, length: u32| {
+            (start..start + length).fold(0, |bits, x| bits << 1 | (modules.get((x, y)) == 0) as u32)
+        };
+        assert_eq!(read(0, 17), 0x1fea8);
+        let columns = symbol.columns as u32;
+        let codeword = |index: u32| {
+            let pattern = read(17 + 17 * index, 17);
+            CLUSTER_PATTERNS[row % 3]
+                .iter()
+                .position(|candidate| *candidate == pattern)
+                .unwrap() as u32
+        };
+        let (left, right) = symbol.row_indicators(row);
+        assert_eq!((codeword(0), codeword(columns + 1)), (left, right));
+        for column in 0..columns {
+            assert_eq!(
+                codeword(column + 1),
+                symbol.codewords[row * columns as usize + column as usize]
+            );
+        }
+        assert_eq!(read(17 * (columns + 3), 18), 0x3fa29);
+    }
+}
+#[test]
+fn test_model1_symbol() {
+    use qrcode::config::{Model, ModuleKind};
+    use qrcode::qr_code::QRcodeOptions;
+    use qrcode::render::Symbol;
+    let options = QRcodeOptions {
+        version: 1,
+        error_level: config::ErrorLevel::M,
+        mask: config::Mask::_010,
+        model: Model::Model1,
+        ..QRcodeOptions::default()
+    };
+    let mut qrcode = QRcode::with_options("QR Model 1", options).unwrap();
+    qrcode.render();
+    assert_eq!(qrcode.rs.create_format_string(), "010001001001011");
+    let symbol = [
+        "XXXXXXX XX XX XXXXXXX",
+        "X     X XX  X X     X",
+        "X XXX X     X X XXX X",
+        "X XXX X XX X  X XXX X",
+        "X XXX X  X  X X XXX X",
+        "X     X  XX X X     X",
+        "XXXXXXX X X X XXXXXXX",
+        "        X X X        ",
+        " X   XX  XXX  X  X XX",
+        "X X XX   X X X X XXX ",
+        "X   X X   X  XXX X   ",
+        "XX XXX XXX XX  X  XX ",
+        "   X XXXX XX  XX XXX ",
+        "        XX X    X X X",
+        "XXXXXXX  XXX  X   X X",
+        "X     X X   XX       ",
+        "X XXX X     X X   X  ",
+        "X XXX X  X X         ",
+        "X XXX X  XXX  X   XX ",
+        "X     X XX  XXX XXX  ",
+        "XXXXXXX  X  X X  XX  ",
+    ];
+    let modules = qrcode.modules();
+    for (y, row) in symbol.iter().enumerate() {
+        for (x, module) in row.chars().enumerate() {
+            let dark = modules.get((x as u32, y as u32)) == 0;
+            assert_eq!(dark, module == 'X', "module ({}, {})", x, y);
+        }
+    }
+
+    let options = QRcodeOptions {
+        version: 2,
+        ..options
+    };
+    let mut qrcode = QRcode::with_options("extension patterns", options).unwrap();
+    qrcode.render();
+    let modules = qrcode.modules();
+    assert_eq!(modules.size(), (25, 25));
+    // Extension patterns at (23, 13) on the right edge and (13, 23), turned,
+    // on the bottom edge; no alignment pattern at (18, 18).
+    let pattern = [[0, 0], [1, 0], [1, 0], [0, 0]];
+    for (y, row) in pattern.iter().enumerate() {
+        for (x, value) in row.iter().enumerate() {
+            let (x, y) = (x as u32, y as u32);
+            assert_eq!(modules.get((23 + x, 13 + y)), *value);
+            assert_eq!(modules.get((13 + y, 23 + x)), *value);
+        }
+    }
+    assert_ne!(
+        (16..21).map(|x| modules.get((x, 18))).collect::<Vec<_>>(),
+        [0, 1, 0, 1, 0]
+    );
+    let kinds = qrcode.module_kinds();
+    let mut extension = 0;
+    for y in 0..25 {
+        for x in 0..25 {
+            match kinds.get((x, y)) {
+                ModuleKind::Empty => panic!("module ({}, {}) has no kind", x, y),
+                ModuleKind::Alignment | ModuleKind::Version => panic!("Model 2 pattern"),
+                ModuleKind::Extension => extension += 1,
+                _ => {}
+            }
+        }
+    }
+    assert_eq!(extension, 2 * 8);
+    assert_eq!(qrcode.placement().len(), qrcode.rs.bits.len());
+    assert_eq!(kinds.get((24, 16)), ModuleKind::Extension);
+    assert_eq!(kinds.get((13, 24)), ModuleKind::Extension);
+    assert!(matches!(
+        QRcode::with_options(
+            "",
+            QRcodeOptions {
+                version: 15,
+                ..options
+            }
+        ),
+        Err(qrcode::error::Error::InvalidOption(_))
+    ));
+    assert_eq!(
+        QRcode::with_options(&"x".repeat(500), options).err(),
+        Some(qrcode::error::Error::DataTooLong)
+    );
+}
+#[test]
+fn test_module_kinds() {
+    use qrcode::config::ModuleKind;
+    let mut qrcode = QRcode::new("module kinds", 7, config::ErrorLevel::M, config::Mask::_100);
+    qrcode.render();
+    let kinds = qrcode.module_kinds();
+    let (size_x, size_y) = kinds.size();
+    let mut data_bits = 0;
+    for y in 0..size_y {
+        for x in 0..size_x {
+            match kinds.get((x, y)) {
+                ModuleKind::Empty => panic!("module ({}, {}) has no kind", x, y),
+                ModuleKind::Data { .. } => data_bits += 1,
+                _ => {}
+            }
+        }
+    }
+    assert_eq!(data_bits, 124 * 8);
+    assert_eq!(kinds.size(), (45, 45));
+    assert_eq!(kinds.get((0, 0)), ModuleKind::Finder);
+    assert_eq!(kinds.get((7, 7)), ModuleKind::Separator);
+}
+#[test]
+fn test_encoding_trace() {
+    let qrcode = QRcode::new("HELLO WORLD", 1, config::ErrorLevel::M, config::Mask::_100);
+    let trace = qrcode.trace();
+    assert_eq!(trace.mode_indicator, "0100");
+    assert_eq!(trace.length_bits, "00001011");
+    assert_eq!(trace.blocks.len(), 1);
+    assert_eq!(
+        trace.blocks[0].generator,
+        vec![0, 251, 67, 46, 61, 118, 70, 64, 94, 32, 45]
+    );
+    assert_eq!(trace.mask_penalties.len(), 8);
+    assert!(trace.to_markdown().contains("| **100** |"));
+}
+
+#[test]
+fn test_placement_animation() {
+    let mut qrcode = QRcode::new(
+        "placement order",
+        5,
+        config::ErrorLevel::Q,
+        config::Mask::_010,
+    );
+    qrcode.render();
+    assert_eq!(qrcode.placement().len(), 134 * 8 + 7);
+    assert_eq!(qrcode.placement()[0], (36, 36));
+    assert_eq!(qrcode.placement()[1], (35, 36));
+    assert_eq!(qrcode.placement()[2], (36, 35));
+    let mut frames = qrcode::placement::to_frames(&qrcode, 4);
+    assert_eq!(frames.next().unwrap().matches("hsl(").count(), 8);
+    let last = frames.last().unwrap();
+    assert_eq!(last.matches("hsl(").count(), 134 * 8 + 7);
+    assert_eq!(qrcode::placement::to_frames(&qrcode, 4).count(), 135);
+    let svg = qrcode::placement::to_animated_svg(&qrcode, 4, 0.1);
+    assert_eq!(svg.matches("<set ").count(), 134 * 8 + 7);
+}
+
+#[test]
+fn test_terminal_renderers() {
+    let mut qrcode = QRcode::new("terminal", 1, config::ErrorLevel::L, config::Mask::_000);
+    qrcode.render();
+    let blocks = qrcode::render::to_half_blocks(&qrcode, false);
+    let lines: Vec<&str> = blocks.lines().collect();
+    assert_eq!(lines.len(), 15);
+    assert!(lines.iter().all(|line| line.chars().count() == 29));
+    assert_eq!(lines[0], " ".repeat(29));
+    assert!(lines[2].starts_with("    █▀▀▀▀▀█ "));
+    let inverted = qrcode::render::to_half_blocks(&qrcode, true);
+    assert_eq!(inverted.lines().next().unwrap(), "█".repeat(29));
+    let ascii = qrcode::render::to_ascii(&qrcode);
+    assert_eq!(ascii.lines().count(), 29);
+    assert!(ascii
+        .lines()
+        .nth(4)
+        .unwrap()
+        .starts_with("        ##############  "));
+    assert!(qrcode::render::to_ansi(&qrcode).contains("\x1b[48;2;0;0;0m"));
+    assert_eq!(qrcode.to_string(), blocks);
+}
+
+#[test]
+fn test_inline_images() {
+    let mut qrcode = QRcode::new("inline", 1, config::ErrorLevel::L, config::Mask::_000);
+    qrcode.render();
+    let bitmap = qrcode::bitmap::Bitmap::from_symbol(&qrcode, 2);
+    assert_eq!((bitmap.width, bitmap.height), (58, 58));
+    assert_eq!(bitmap.get(7, 7), 255);
+    assert_eq!(bitmap.get(8, 8), 0);
+    let png = bitmap.to_png();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(qrcode::bitmap::base64(b"Man"), "TWFu");
+    assert_eq!(qrcode::bitmap::base64(b"Ma"), "TWE=");
+    let sixel = render::to_sixel(&qrcode, 2);
+    assert!(sixel.starts_with("\x1bPq\"1;1;58;58"));
+    assert_eq!(sixel.matches('-').count(), 10);
+    assert!(render::to_kitty(&qrcode, 2).starts_with("\x1b_Ga=T,f=100,m=1;iVBORw0KGgo"));
+    assert!(render::to_iterm(&qrcode, 2).contains("width=58px"));
+}
+
+#[test]
+fn test_netpbm_round_trip() {
+    use qrcode::error::Error;
+    let mut qrcode = QRcode::new("netpbm", 2, config::ErrorLevel::M, config::Mask::_011);
+    qrcode.render();
+    let expected = qrcode::netpbm::to_modules(
+        &qrcode::netpbm::read(&qrcode::netpbm::to_pgm(&qrcode, 1, true).unwrap()).unwrap(),
+        1,
+    )
+    .unwrap();
+    assert_eq!(expected.size(), (33, 33));
+    assert_eq!(expected.get((4, 4)), 0);
+    for (raw, scale) in [(false, 1), (true, 3), (true, 5)] {
+        for image in [
+            qrcode::netpbm::to_pbm(&qrcode, scale, raw).unwrap(),
+            qrcode::netpbm::to_pgm(&qrcode, scale, raw).unwrap(),
+        ] {
+            let modules =
+                qrcode::netpbm::to_modules(&qrcode::netpbm::read(&image).unwrap(), scale).unwrap();
+            assert_eq!(format!("{:?}", modules), format!("{:?}", expected));
+        }
+    }
+    let ppm = qrcode::netpbm::to_ppm(&qrcode, 2, true, [0, 0, 128], [255, 255, 255]).unwrap();
+    assert!(ppm.starts_with(b"P6\n66 66\n255\n"));
+    assert_eq!(ppm.len(), 13 + 66 * 66 * 3);
+    assert_eq!(
+        qrcode::netpbm::read(b"P1\n# comment\n2 2\n1001")
+            .unwrap()
+            .get((1, 0)),
+        255
+    );
+    assert!(qrcode::netpbm::read(b"P6\n1 1\n255\n").is_err());
+    for header in [
+        &b"P5\n4294967295 4294967295\n65535\n\0\0"[..],
+        b"P4\n100000 100000\n\0",
+        b"P2\n3 3\n255\n0 0 0",
+    ] {
+        assert_eq!(
+            qrcode::netpbm::read(header).err(),
+            Some(Error::InvalidImage("truncated raster"))
+        );
+    }
+    assert_eq!(
+        qrcode::netpbm::to_pbm(&qrcode, 0, true).err(),
+        Some(Error::InvalidOption("scale must be at least 1"))
+    );
+    assert!(qrcode::netpbm::to_pgm(&qrcode, 0, false).is_err());
+    assert!(qrcode::netpbm::to_ppm(&qrcode, 0, true, [0; 3], [255; 3]).is_err());
+    assert!(qrcode::netpbm::to_modules(&expected, 0).is_err());
+}
+
+#[test]
+fn test_print_output() {
+    use qrcode::print::{Cmyk, Length, PrintOptions};
+    let mut qrcode = QRcode::new("print", 1, config::ErrorLevel::Q, config::Mask::_101);
+    qrcode.render();
+    let options = PrintOptions {
+        width: Length::Points(290.0),
+        bleed: Length::Millimetres(25.4 / 72.0 * 9.0),
+        light: Some(Cmyk::WHITE),
+        ..Default::default()
+    };
+    let eps = qrcode::print::to_eps(&qrcode, &options);
+    assert!(eps.contains("%%BoundingBox: 0 0 308 308\n"));
+    assert!(eps.contains("0 0 0 1 setcmykcolor\n49 259 moveto\n119 259 lineto\n119 189 lineto\n"));
+    let pdf = String::from_utf8(qrcode::print::to_pdf(&qrcode, &options)).unwrap();
+    assert!(pdf.contains("/MediaBox [0 0 308 308] /BleedBox [0 0 308 308] /TrimBox [9 9 299 299]"));
+    let xref: usize = pdf.lines().rev().nth(1).unwrap().parse().unwrap();
+    assert!(pdf[xref..].starts_with("xref\n0 5\n"));
+    let offset: usize = pdf[xref..].lines().nth(6).unwrap()[..10].parse().unwrap();
+    assert!(pdf[offset..].starts_with("4 0 obj"));
+    qrcode.quiet_zone = 0;
+    let trimmed = qrcode::print::to_eps(&qrcode, &options);
+    assert!(trimmed.contains("0 0 0 1 setcmykcolor\n9 299 moveto\n"));
+    let padded = PrintOptions {
+        quiet_zone: Some(4),
+        ..options
+    };
+    assert_eq!(qrcode::print::to_eps(&qrcode, &padded), eps);
+}
+
+#[test]
+fn test_contour_trace() {
+    let ring = vec![
+        vec![true, true, true, false],
+        vec![true, false, true, false],
+        vec![true, true, true, true],
+        vec![false, false, false, true],
+    ];
+    let polygons = qrcode::contour::trace(&ring);
+    assert_eq!(
+        polygons,
+        vec![
+            vec![
+                (0, 0),
+                (3, 0),
+                (3, 2),
+                (4, 2),
+                (4, 4),
+                (3, 4),
+                (3, 3),
+                (0, 3)
+            ],
+            vec![(2, 1), (1, 1), (1, 2), (2, 2)],
+        ]
+    );
+    let diagonal = vec![vec![true, false], vec![false, true]];
+    assert_eq!(qrcode::contour::trace(&diagonal).len(), 2);
+    let mut qrcode = QRcode::new("contour", 25, config::ErrorLevel::H, config::Mask::_110);
+    qrcode.render();
+    let svg = qrcode::render::to_svg(&qrcode, 1);
+    assert!(svg.matches('z').count() < svg.matches('L').count() / 4);
+    assert!(svg.contains("M4 4L11 4L11 11L4 11z"));
+}
+
+#[test]
+fn test_styled_modules() {
+    use qrcode::style::{EyeShape, ModuleShape, Style};
+    let mut qrcode = QRcode::new("styled", 7, config::ErrorLevel::Q, config::Mask::_001);
+    qrcode.render();
+    assert_eq!(
+        qrcode::style::finder_positions(&qrcode),
+        vec![(0, 0), (38, 0), (0, 38)]
+    );
+    let plain = qrcode::bitmap::Bitmap::from_symbol(&qrcode, 4);
+    let square = qrcode::style::to_bitmap(&qrcode, &Style::default(), 4);
+    assert_eq!((square.width, square.height), (plain.width, plain.height));
+    assert!(square.pixels == plain.pixels);
+    let style = Style {
+        module: ModuleShape::Connected,
+        finder_outer: EyeShape::Rounded,
+        finder_inner: EyeShape::Circle,
+    };
+    let rounded = qrcode::style::to_bitmap(&qrcode, &style, 10);
+    assert_eq!(rounded.get(40, 40), 255);
+    assert_eq!(rounded.get(75, 75), 0);
+    assert_eq!(rounded.get(41, 75), 0);
+    let colors = qrcode::color::Colors::default();
+    let svg = qrcode::style::to_svg(&qrcode, &style, &colors, 10).unwrap();
+    assert!(svg.contains("M61 40H89A21 21 0 0 1 110 61"));
+}
+
+#[test]
+fn test_colors() {
+    use qrcode::color::{contrast_ratio, Colors, Paint, Rgba};
+    use qrcode::style::Style;
+    assert!((contrast_ratio(&Rgba::BLACK, &Rgba::WHITE) - 21.0).abs() < 0.01);
+    let mut qrcode = QRcode::new("colors", 7, config::ErrorLevel::M, config::Mask::_000);
+    qrcode.render();
+    let navy = Rgba::rgb(0, 0, 128);
+    let colors = Colors {
+        data: Paint::Linear {
+            from: Rgba::BLACK,
+            to: navy,
+            angle: 0.0,
+        },
+        finder_outer: Paint::Solid(navy),
+        finder_inner: Paint::Radial {
+            center: Rgba::BLACK,
+            edge: Rgba::rgb(128, 0, 0),
+        },
+        alignment: Paint::Solid(Rgba::rgb(0, 96, 0)),
+        background: None,
+    };
+    assert!(colors.check_contrast().unwrap() > 4.0);
+    let svg = qrcode::style::to_svg(&qrcode, &Style::default(), &colors, 10).unwrap();
+    assert!(svg.contains("<linearGradient id=\"data\" gradientUnits=\"userSpaceOnUse\" x1=\"0\" y1=\"265\" x2=\"530\" y2=\"265\">"));
+    assert!(svg.contains("fill=\"url(#finder-inner)\""));
+    assert!(!svg.contains("<rect"));
+    let image = qrcode::style::to_rgba(&qrcode, &Style::default(), &colors, 10).unwrap();
+    assert_eq!(image.get(0, 0), [0, 0, 0, 0]);
+    assert_eq!(image.get(45, 45), [0, 0, 128, 255]);
+    assert_eq!(image.to_png()[25], 6);
+    let washed = Colors {
+        data: Paint::Solid(Rgba::rgb(200, 200, 200)),
+        ..Colors::default()
+    };
+    assert!(washed.check_contrast().is_err());
+    let inverted = Colors {
+        background: Some(Rgba::BLACK),
+        data: Paint::Solid(Rgba::WHITE),
+        ..Colors::default()
+    };
+    assert!(qrcode::style::to_svg(&qrcode, &Style::default(), &inverted, 10).is_err());
+}
+
+#[test]
+fn test_logo_area() {
+    use qrcode::logo::{Logo, LogoImage};
+    use qrcode::qr_code::QRcodeOptions;
+    let options = QRcodeOptions {
+        version: 10,
+        ..Default::default()
+    };
+    let (qrcode, area) = qrcode::logo::with_logo("logo", options, &Logo::default()).unwrap();
+    assert_eq!(
+        qrcode.rs.error_level as usize,
+        config::ErrorLevel::H as usize
+    );
+    assert_eq!((area.x, area.y, area.width, area.height), (22, 22, 13, 13));
+    assert_eq!(area.damaged.len(), 8);
+    assert!(area
+        .damaged
+        .iter()
+        .all(|damaged| *damaged > 0 && *damaged <= 8));
+    assert_eq!(area.correctable, vec![14; 8]);
+    assert!(matches!(
+        qrcode.module_kinds().get((22, 22)),
+        config::ModuleKind::ErrorCorrection { .. }
+    ));
+    assert_eq!(qrcode::render::Symbol::modules(&qrcode).get((22, 22)), 1);
+    let large = Logo {
+        width: 0.4,
+        height: 0.4,
+        ..Default::default()
+    };
+    assert!(qrcode::logo::with_logo("logo", options, &large).is_err());
+    let svg = qrcode::logo::to_svg(&qrcode, &area, &LogoImage::Svg("<svg/>".to_string()), 4);
+    assert!(svg.contains("<image x=\"104\" y=\"104\" width=\"52\" height=\"52\" href=\"data:image/svg+xml;base64,PHN2Zy8+\"/></svg>"));
+    let red = qrcode::bitmap::RgbaImage {
+        width: 1,
+        height: 1,
+        pixels: vec![255, 0, 0, 255],
+    };
+    let image = qrcode::logo::to_rgba(&qrcode, &area, &red, 2);
+    assert_eq!(image.get(2 * (4 + 28), 2 * (4 + 28)), [255, 0, 0, 255]);
+    let empty = qrcode::bitmap::RgbaImage {
+        width: 0,
+        height: 0,
+        pixels: Vec::new(),
+    };
+    let bare = qrcode::logo::to_rgba(&qrcode, &area, &empty, 2);
+    let bitmap = qrcode::bitmap::Bitmap::from_symbol(&qrcode, 2);
+    let gray = bitmap.get(2 * (4 + 28), 2 * (4 + 28));
+    assert_eq!(
+        bare.get(2 * (4 + 28), 2 * (4 + 28)),
+        [gray, gray, gray, 255]
+    );
+    let unsafe_logo = Logo {
+        safety: 1.5,
+        ..Default::default()
+    };
+    assert_eq!(
+        qrcode::logo::with_logo("logo", options, &unsafe_logo).err(),
+        Some(qrcode::error::Error::InvalidOption(
+            "logo safety is 0.0..1.0"
+        ))
+    );
+    let flat = Logo {
+        height: 0.0,
+        ..Default::default()
+    };
+    let (plain, area) = qrcode::logo::with_logo("logo", options, &flat).unwrap();
+    assert_eq!((area.width, area.height), (0, 0));
+    assert_eq!(area.damaged, vec![0; 8]);
+    let mut expected = QRcode::with_options(
+        "logo",
+        QRcodeOptions {
+            error_level: config::ErrorLevel::H,
+            ..options
+        },
+    )
+    .unwrap();
+    expected.render();
+    assert_eq!(format!("{:?}", plain), format!("{:?}", expected));
+    let svg = qrcode::logo::to_svg(&plain, &area, &LogoImage::Svg("<svg/>".to_string()), 4);
+    assert!(!svg.contains("<image"));
+}
+
+#[test]
+fn test_quiet_zone() {
+    use qrcode::qr_code::QRcodeOptions;
+    use qrcode::render::Symbol;
+    let mut qrcode = QRcode::new("quiet zone", 1, config::ErrorLevel::L, config::Mask::_000);
+    qrcode.render();
+    assert_eq!(qrcode.modules().size(), (21, 21));
+    assert_eq!(qrcode.symbol_size(), (21, 21));
+    assert_eq!(qrcode.rendered_size(), (29, 29));
+    let options = QRcodeOptions {
+        quiet_zone: 0,
+        ..Default::default()
+    };
+    let mut bare = QRcode::with_options("quiet zone", options).unwrap();
+    bare.render();
+    assert_eq!(bare.rendered_size(), (21, 21));
+    assert!(qrcode::render::to_svg(&bare, 1)
+        .starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"21\""));
+    assert!(qrcode::render::to_ascii(&bare).starts_with(&("#".repeat(14) + "  ")));
+    qrcode.quiet_zone = 2;
+    assert_eq!(qrcode::bitmap::Bitmap::from_symbol(&qrcode, 1).width, 25);
+}
+
+#[test]
+fn test_module_grid() {
+    use qrcode::render::Symbol;
+    let mut qrcode = QRcode::new("grid", 1, config::ErrorLevel::M, config::Mask::_010);
+    qrcode.render();
+    assert_eq!((qrcode.width(), qrcode.height()), (21, 21));
+    assert!(qrcode.is_dark(0, 0));
+    assert!(!qrcode.is_dark(7, 0));
+    assert!(!qrcode.is_dark(21, 0));
+    let grid = qrcode.to_bool_grid();
+    let rows: Vec<Vec<bool>> = qrcode.rows().map(|row| row.collect()).collect();
+    assert_eq!(grid, rows);
+    assert_eq!(grid[6][8..13], [true, false, true, false, true]);
+    let packed = qrcode.to_bytes_packed();
+    assert_eq!(packed.len(), 3 * 21);
+    assert_eq!(packed[0], 0b1111_1110);
+    assert_eq!(packed[2] & 0b0000_0111, 0);
+}
+
+#[test]
+fn test_error_correction_tables() {
+    use qrcode::config::{ByteNameOfMode, ErrorLevel, REMINDER};
+    use qrcode::utils::{full_capacity, get_codewords_number};
+    // Data codewords of versions 1 to 40 at L, M, Q and H (ISO/IEC 18004 table 7).
+    let data_codewords: [[u32; 4]; 40] = [
+        [19, 16, 13, 9],
+        [34, 28, 22, 16],
+        [55, 44, 34, 26],
+        [80, 64, 48, 36],
+        [108, 86, 62, 46],
+        [136, 108, 76, 60],
+        [156, 124, 88, 66],
+        [194, 154, 110, 86],
+        [232, 182, 132, 100],
+        [274, 216, 154, 122],
+        [324, 254, 180, 140],
+        [370, 290, 206, 158],
+        [428, 334, 244, 180],
+        [461, 365, 261, 197],
+        [523, 415, 295, 223],
+        [589, 453, 325, 253],
+        [647, 507, 367, 283],
+        [721, 563, 397, 313],
+        [795, 627, 445, 341],
+        [861, 669, 485, 385],
+        [932, 714, 512, 406],
+        [1006, 782, 568, 442],
+        [1094, 860, 614, 464],
+        [1174, 914, 664, 514],
+        [1276, 1000, 718, 538],
+        [1370, 1062, 754, 596],
+        [1468, 1128, 808, 628],
+        [1531, 1193, 871, 661],
+        [1631, 1267, 911, 701],
+        [1735, 1373, 985, 745],
+        [1843, 1455, 1033, 793],
+        [1955, 1541, 1115, 845],
+        [2071, 1631, 1171, 901],
+        [2191, 1725, 1231, 961],
+        [2306, 1812, 1286, 986],
+        [2434, 1914, 1354, 1054],
+        [2566, 1992, 1426, 1096],
+        [2702, 2102, 1502, 1142],
+        [2812, 2216, 1582, 1222],
+        [2956, 2334, 1666, 1276],
+    ];
+    let levels = [ErrorLevel::L, ErrorLevel::M, ErrorLevel::Q, ErrorLevel::H];
+    for (version, row) in (1..=40).zip(data_codewords.iter()) {
+        for (level, expected) in levels.iter().zip(row) {
+            assert_eq!(
+                get_codewords_number(version, level),
+                *expected,
+                "version {} level {:?}",
+                version,
+                level
+            );
+        }
+        let remainder = match version {
+            2..=6 => 7,
+            14..=20 | 28..=34 => 3,
+            21..=27 => 4,
+            _ => 0,
+        };
+        assert_eq!(REMINDER[version as usize - 1], remainder);
+    }
+    assert_eq!(full_capacity(1, &ErrorLevel::L, &ByteNameOfMode::Byte), 17);
+    assert_eq!(
+        full_capacity(40, &ErrorLevel::L, &ByteNameOfMode::Byte),
+        2953
+    );
+    assert_eq!(
+        full_capacity(40, &ErrorLevel::H, &ByteNameOfMode::Numeric),
+        3057
+    );
+    assert_eq!(full_capacity(1, &ErrorLevel::H, &ByteNameOfMode::Alpha), 10);
+}
+
+#[test]
+fn test_selection_policies() {
+    use config::{ErrorLevel, Selection};
+    use qrcode::error::Error;
+    use qrcode::qr_code::QRcodeOptions;
+    let select = |data: &str, options: QRcodeOptions| {
+        QRcode::with_options(data, options)
+            .map(|qrcode| (qrcode.rs.version, qrcode.rs.error_level as usize))
+    };
+    let options = QRcodeOptions {
+        error_level: ErrorLevel::M,
+        ..Default::default()
+    };
+    assert_eq!(
+        select("HELLO WORLD", options),
+        Ok((1, ErrorLevel::M as usize))
+    );
+    assert_eq!(
+        select(&"x".repeat(20), options),
+        Ok((2, ErrorLevel::M as usize))
+    );
+    let boost = QRcodeOptions {
+        selection: Selection::BoostLevel,
+        ..options
+    };
+    assert_eq!(
+        select("HELLO WORLD", boost),
+        Ok((1, ErrorLevel::Q as usize))
+    );
+    let fixed = QRcodeOptions {
+        version: 2,
+        selection: Selection::FixedVersion,
+        ..options
+    };
+    assert_eq!(
+        select("HELLO WORLD", fixed),
+        Ok((2, ErrorLevel::H as usize))
+    );
+    assert_eq!(select(&"x".repeat(40), fixed), Err(Error::DataTooLong));
+    let capped = QRcodeOptions {
+        max_version: 3,
+        ..options
+    };
+    assert_eq!(select(&"x".repeat(100), capped), Err(Error::DataTooLong));
+    let inverted = QRcodeOptions {
+        version: 5,
+        max_version: 3,
+        ..options
+    };
+    assert!(matches!(
+        select("x", inverted),
+        Err(Error::InvalidOption(_))
+    ));
+}
+
+#[test]
+fn test_capacity() {
+    use config::{ByteNameOfMode, ErrorLevel};
+    use qrcode::capacity::{self, Segment};
+    let bytes_l = [
+        17, 32, 53, 78, 106, 134, 154, 192, 230, 271, 321, 367, 425, 458, 520, 586, 644, 718, 792,
+        858, 929, 1003, 1091, 1171, 1273, 1367, 1465, 1528, 1628, 1732, 1840, 1952, 2068, 2188,
+        2303, 2431, 2563, 2699, 2809, 2953,
+    ];
+    let bytes_h = [
+        7, 14, 24, 34, 44, 58, 64, 84, 98, 119, 137, 155, 177, 194, 220, 250, 280, 310, 338, 382,
+        403, 439, 461, 511, 535, 593, 625, 658, 698, 742, 790, 842, 898, 958, 983, 1051, 1093,
+        1139, 1219, 1273,
+    ];
+    for version in 1..=40 {
+        let index = version as usize - 1;
+        assert_eq!(
+            capacity::characters(ByteNameOfMode::Byte, version, ErrorLevel::L),
+            bytes_l[index]
+        );
+        assert_eq!(
+            capacity::characters(ByteNameOfMode::Byte, version, ErrorLevel::H),
+            bytes_h[index]
+        );
+    }
+    let characters = |mode, version, level| capacity::characters(mode, version, level);
+    assert_eq!(characters(ByteNameOfMode::Numeric, 1, ErrorLevel::L), 41);
+    assert_eq!(characters(ByteNameOfMode::Alpha, 1, ErrorLevel::L), 25);
+    assert_eq!(characters(ByteNameOfMode::Kanji, 1, ErrorLevel::L), 10);
+    assert_eq!(characters(ByteNameOfMode::Numeric, 40, ErrorLevel::H), 3057);
+    assert_eq!(characters(ByteNameOfMode::Alpha, 40, ErrorLevel::M), 3391);
+    assert_eq!(characters(ByteNameOfMode::Kanji, 40, ErrorLevel::Q), 1024);
+    assert_eq!(capacity::data_codewords(40, ErrorLevel::L), 2956);
+    let segments = [
+        Segment {
+            mode: ByteNameOfMode::Numeric,
+            characters: 8,
+        },
+        Segment {
+            mode: ByteNameOfMode::Alpha,
+            characters: 5,
+        },
+    ];
+    assert_eq!(capacity::bits(&segments, 1), 41 + 41);
+    assert!(capacity::fits(&segments, 1, ErrorLevel::M));
+    assert!(!capacity::fits(&segments, 1, ErrorLevel::H));
+    let url = "https://youtu.be/dQw4w9WgXcQ";
+    assert_eq!(capacity::smallest_version_for(url, ErrorLevel::H), Some(4));
+    assert_eq!(capacity::characters_left(url, 4, ErrorLevel::H), Some(6));
+    assert_eq!(capacity::characters_left(url, 3, ErrorLevel::H), None);
+    assert_eq!(
+        capacity::smallest_version_for(&"x".repeat(2954), ErrorLevel::L),
+        None
+    );
+    for (data, version) in [("x".repeat(17), 1), ("x".repeat(2953), 40)] {
+        let mut qrcode = QRcode::new(&data, 1, ErrorLevel::L, config::Mask::_000);
+        qrcode.render();
+        assert_eq!(qrcode.rs.version, version);
+    }
+}
+
+#[test]
+fn test_encoding_report() {
+    use qrcode::report::BlockGroup;
+    let mut qrcode = QRcode::new(
+        "https://youtu.be/dQw4w9WgXcQ",
+        1,
+        config::ErrorLevel::H,
+        config::Mask::_100,
+    );
+    qrcode.render();
+    let report = qrcode.report();
+    assert_eq!(report.segments[0].1, 4 + 8 + 28 * 8);
+    assert_eq!((report.used_bits, report.available_bits), (236, 288));
+    assert_eq!((report.terminator_bits, report.pad_codewords), (4, 6));
+    assert_eq!(report.remainder_bits, 7);
+    assert_eq!(
+        report.groups,
+        vec![BlockGroup {
+            blocks: 4,
+            data_codewords: 9,
+            error_correction_codewords: 16
+        }]
+    );
+    assert_eq!(report.total_penalty(), qrcode.penalty().iter().sum::<u32>());
+    let content = qrcode.rs.generate_content();
+    assert_eq!(&content[30..], &[236, 17, 236, 17, 236, 17]);
+    let json = report.to_json();
+    assert!(json.starts_with("{\"version\":4,\"error_level\":\"H\",\"mask\":\"100\",\"segments\":[{\"mode\":\"Byte\",\"characters\":28,\"bits\":236}]"));
+    let two_groups = QRcode::new("x", 5, config::ErrorLevel::Q, config::Mask::_000).report();
+    assert_eq!(
+        two_groups
+            .groups
+            .iter()
+            .map(|group| (group.blocks, group.data_codewords))
+            .collect::<Vec<_>>(),
+        vec![(2, 15), (2, 16)]
+    );
+    // 17 bytes after the 4 leading bits of Model 1 fill version 1-L to the
+    // last bit, leaving no room for the terminator.
+    let options = qrcode::qr_code::QRcodeOptions {
+        version: 1,
+        error_level: config::ErrorLevel::L,
+        model: config::Model::Model1,
+        ..Default::default()
+    };
+    let full = QRcode::with_options(&"x".repeat(17), options).unwrap();
+    let report = full.report();
+    assert_eq!(report.version, 1);
+    assert_eq!((report.used_bits, report.available_bits), (152, 152));
+    assert_eq!((report.terminator_bits, report.pad_codewords), (0, 0));
+    assert_eq!(full.rs.generate_content().len(), 19);
+}
+
+#[test]
+fn test_binary_round_trip() {
+    use qrcode::decode::decode;
+    use qrcode::qr_code::{Matrix, QRcodeOptions};
+    use qrcode::render::Symbol;
+    let binary: Vec<u8> = (0..=255).rev().collect();
+    let options = QRcodeOptions {
+        error_level: config::ErrorLevel::Q,
+        ..Default::default()
+    };
+    let mut qrcode = QRcode::from_reader(&binary[..], options).unwrap();
+    qrcode.render();
+    let decoded = decode(qrcode.modules()).unwrap();
+    assert_eq!(decoded.bytes, binary);
+    assert_eq!(decoded.text, None);
+    assert_eq!(decoded.corrected, 0);
+    assert_eq!(decoded.version, qrcode.rs.version);
+    let too_long = vec![0; 2954];
+    assert_eq!(
+        QRcode::from_reader(&too_long[..], options).err(),
+        Some(qrcode::error::Error::DataTooLong)
+    );
+    let mut qrcode = QRcode::new("łąka", 1, config::ErrorLevel::H, config::Mask::_010);
+    qrcode.render();
+    let damaged = |codewords: usize| {
+        let mut modules = Matrix::create(21, 21, &1);
+        for y in 0..21 {
+            for x in 0..21 {
+                modules.put((x, y), qrcode.modules().get((x, y)));
+            }
+        }
+        for point in qrcode.placement().iter().step_by(8).take(codewords) {
+            modules.put(*point, 1 - modules.get(*point));
+        }
+        decode(&modules)
+    };
+    let decoded = damaged(8).unwrap();
+    assert_eq!(decoded.text.as_deref(), Some("łąka"));
+    assert_eq!(decoded.corrected, 8);
+    assert!(damaged(9).is_err());
+    // Bytes written under a numeric or alphanumeric mode indicator: valid
+    // codewords holding values those modes cannot encode.
+    let misread = |data: &[u8], mode: config::ByteNameOfMode| {
+        let mut qrcode = QRcode::from_bytes(data, options).unwrap();
+        qrcode.rs.mode = mode;
+        qrcode.rs.generate_data_bits();
+        qrcode.render();
+        decode(qrcode.modules()).err()
+    };
+    let numeric = Some(qrcode::error::Error::InvalidImage(
+        "numeric group out of range",
+    ));
+    assert_eq!(
+        misread(&[0xff, 0xff], config::ByteNameOfMode::Numeric),
+        numeric
+    );
+    let alphanumeric = Some(qrcode::error::Error::InvalidImage(
+        "alphanumeric value out of range",
+    ));
+    assert_eq!(
+        misread(&[0xff], config::ByteNameOfMode::Alpha),
+        alphanumeric
+    );
+    assert_eq!(
+        misread(&[0xff, 0xff], config::ByteNameOfMode::Alpha),
+        alphanumeric
+    );
+}
+
+#[test]
+fn test_bch_codes() {
+    use qrcode::bch::{self, FORMAT_CODEWORDS, VERSION_CODEWORDS};
+    use qrcode::render::Symbol;
+    assert_eq!(bch::format_codeword(0b01_000), 0b111_0111_1100_0100);
+    assert_eq!(bch::version_codeword(7), 0x07c94);
+    assert_eq!(bch::version_codeword(40), 0x28c69);
+    for (codewords, distance) in [(&FORMAT_CODEWORDS[..], 7), (&VERSION_CODEWORDS[..], 8)] {
+        for (i, a) in codewords.iter().enumerate() {
+            for b in &codewords[i + 1..] {
+                assert!((a ^ b).count_ones() >= distance);
+            }
+        }
+    }
+    assert_eq!(
+        bch::decode_format(FORMAT_CODEWORDS[13] ^ 0b100_0000_0010_0001),
+        Some((13, 3))
+    );
+    assert_eq!(bch::decode_version(0x28c69 ^ 0b111), Some((40, 3)));
+    assert_eq!(bch::decode_version(0), None);
+    let mut qrcode = QRcode::new("hi", 1, config::ErrorLevel::L, config::Mask::_001);
+    qrcode.render();
+    let format = qrcode.rs.create_format_string();
+    assert_eq!(format, "111001011110011");
+    assert_eq!(qrcode.modules().get((8, 8)), 0);
+    assert_eq!(qrcode.modules().get((7, 8)), 1);
+}
+
+#[test]
+fn test_finder_locator() {
+    use qrcode::locate::locate;
+    use qrcode::netpbm;
+    use qrcode::qr_code::Matrix;
+    let mut qrcode = QRcode::new("locate me", 7, config::ErrorLevel::M, config::Mask::_011);
+    qrcode.render();
+    let mut image = netpbm::read(&netpbm::to_pgm(&qrcode, 3, true).unwrap()).unwrap();
+    let location = locate(&image, 128).unwrap();
+    assert_eq!((location.version, location.dimension), (7, 45));
+    assert_eq!((location.top_left.x, location.top_left.y), (22.5, 22.5));
+    assert_eq!(
+        (location.top_right.x, location.bottom_left.y),
+        (136.5, 136.5)
+    );
+    assert_eq!(location.module_size, 3.0);
+    assert_eq!(
+        location.corners,
+        [(12.0, 12.0), (147.0, 12.0), (147.0, 147.0), (12.0, 147.0)]
+    );
+    image.rotate();
+    let location = locate(&image, 128).unwrap();
+    assert_eq!(location.rotation, 90.0);
+    assert_eq!(location.corners[0], (147.0, 12.0));
+    // Nearest neighbour rotation by 30 degrees around the image center.
+    let (width, _) = image.size();
+    let size = width * 3 / 2;
+    let mut rotated = Matrix::create(size, size, &255);
+    let (sin, cos) = 30f32.to_radians().sin_cos();
+    for y in 0..size {
+        for x in 0..size {
+            let dx = x as f32 + 0.5 - size as f32 / 2.0;
+            let dy = y as f32 + 0.5 - size as f32 / 2.0;
+            let source_x = cos * dx + sin * dy + width as f32 / 2.0;
+            let source_y = cos * dy - sin * dx + width as f32 / 2.0;
+            if (0.0..width as f32).contains(&source_x) && (0.0..width as f32).contains(&source_y) {
+                rotated.put((x, y), image.get((source_x as u32, source_y as u32)));
+            }
+        }
+    }
+    let location = locate(&rotated, 128).unwrap();
+    assert_eq!(location.version, 7);
+    assert!((location.rotation - 120.0).abs() < 1.0);
+    assert!((location.module_size - 3.0).abs() < 0.2);
+    assert!(locate(&Matrix::create(50, 50, &255), 128).is_err());
+}
+
+#[test]
+fn test_perspective_sampling() {
+    use qrcode::decode::{decode, decode_image};
+    use qrcode::locate::locate;
+    use qrcode::netpbm;
+    use qrcode::qr_code::Matrix;
+    use qrcode::sample::{find_alignment, sample, Perspective};
+    let text = "perspective sampling with alignment refinement";
+    let mut qrcode = QRcode::new(text, 7, config::ErrorLevel::M, config::Mask::_101);
+    qrcode.render();
+    let image = netpbm::read(&netpbm::to_pgm(&qrcode, 4, true).unwrap()).unwrap();
+    let location = locate(&image, 128).unwrap();
+    let modules = sample(&image, 128, &location).unwrap();
+    assert_eq!(decode(&modules).unwrap().text.as_deref(), Some(text));
+    // Alignment center at module (38.5, 38.5) after the 4 module quiet zone.
+    assert_eq!(
+        find_alignment(&image, 128, &location, 45),
+        Some((170.0, 170.0))
+    );
+    // Nearest neighbour warp of the whole image onto a keystoned quadrilateral.
+    let (width, _) = image.size();
+    let side = width as f32;
+    let corners = [
+        (side * 0.15, side * 0.05),
+        (side * 0.95, 0.0),
+        (side * 1.1, side),
+        (0.0, side * 0.9),
+    ];
+    let back = Perspective::quadrilateral_to_quadrilateral(
+        corners,
+        [(0.0, 0.0), (side, 0.0), (side, side), (0.0, side)],
+    );
+    let size = width * 5 / 4;
+    let mut warped = Matrix::create(size, size, &255);
+    for y in 0..size {
+        for x in 0..size {
+            let (source_x, source_y) = back.transform((x as f32 + 0.5, y as f32 + 0.5));
+            if (0.0..side).contains(&source_x) && (0.0..side).contains(&source_y) {
+                warped.put((x, y), image.get((source_x as u32, source_y as u32)));
+            }
+        }
+    }
+    let location = locate(&warped, 128).unwrap();
+    let forward = Perspective::quadrilateral_to_quadrilateral(
+        [(0.0, 0.0), (side, 0.0), (side, side), (0.0, side)],
+        corners,
+    );
+    let expected = forward.transform((170.0, 170.0));
+    let found = find_alignment(&warped, 128, &location, 45).unwrap();
+    assert!((found.0 - expected.0).abs() < 4.0 && (found.1 - expected.1).abs() < 4.0);
+    let decoded = decode_image(&warped, 128).unwrap();
+    assert_eq!((decoded.version, decoded.text.as_deref()), (7, Some(text)));
+    let mut version_1 = QRcode::new("v1", 1, config::ErrorLevel::L, config::Mask::_000);
+    version_1.render();
+    let image = netpbm::read(&netpbm::to_pgm(&version_1, 2, true).unwrap()).unwrap();
+    let location = locate(&image, 128).unwrap();
+    assert_eq!(find_alignment(&image, 128, &location, 21), None);
+    assert_eq!(find_alignment(&image, 128, &location, 7), None);
+    let unsized_location = qrcode::locate::Location {
+        dimension: 15,
+        ..location
+    };
+    let not_a_size = Some(qrcode::error::Error::InvalidImage(
+        "not a QR code symbol size",
+    ));
+    assert_eq!(sample(&image, 128, &unsized_location).err(), not_a_size);
+    assert!(qrcode::sample::perspective(&image, 128, &location, 23).is_err());
+    assert_eq!(
+        decode_image(&image, 128).unwrap().text.as_deref(),
+        Some("v1")
+    );
+}
+
+#[test]
+fn test_binarizers() {
+    use qrcode::binarize::{block_mean, otsu, otsu_threshold, sauvola};
+    use qrcode::decode::decode_image;
+    use qrcode::error::Error;
+    use qrcode::netpbm;
+    use qrcode::qr_code::Matrix;
+    let text = "uneven lighting";
+    let mut qrcode = QRcode::new(text, 3, config::ErrorLevel::M, config::Mask::_010);
+    qrcode.render();
+    let image = netpbm::read(&netpbm::to_pgm(&qrcode, 4, true).unwrap()).unwrap();
+    assert_eq!(otsu_threshold(&image), 128);
+    assert_eq!(otsu_threshold(&Matrix::create(4, 4, &200)), 0);
+    // Light from the right: dark 20 and light 70 on the left edge, 160 and
+    // 255 on the right.
+    let (width, height) = image.size();
+    let mut shaded = Matrix::create(width, height, &0);
+    for y in 0..height {
+        for x in 0..width {
+            let light = x as f32 / (width - 1) as f32;
+            let value = if image.get((x, y)) == 0 {
+                20.0 + 140.0 * light
+            } else {
+                70.0 + 185.0 * light
+            };
+            shaded.put((x, y), value as u8);
+        }
+    }
+    assert!(decode_image(&shaded, 128).is_err());
+    assert!(decode_image(&otsu(&shaded), 128).is_err());
+    for binary in [
+        block_mean(&shaded, 31, 10).unwrap(),
+        sauvola(&shaded, 31, 0.2).unwrap(),
+    ] {
+        assert_eq!(
+            decode_image(&binary, 128).unwrap().text.as_deref(),
+            Some(text)
+        );
+    }
+    assert_eq!(
+        block_mean(&shaded, 30, 10).err(),
+        Some(Error::InvalidOption("window must be odd and at least 3"))
+    );
+    assert!(sauvola(&shaded, 31, 1.5).is_err());
+}
+
+#[test]
+fn test_png_reader() {
+    use qrcode::bitmap::Bitmap;
+    use qrcode::decode::decode_image;
+    use qrcode::error::Error;
+    use qrcode::inflate::zlib_decompress;
+    use qrcode::png;
+    let mut qrcode = QRcode::new("png", 2, config::ErrorLevel::Q, config::Mask::_110);
+    qrcode.render();
+    let bitmap = Bitmap::from_symbol(&qrcode, 3);
+    let image = png::read(&bitmap.to_png()).unwrap();
+    assert_eq!(image.size(), (bitmap.width, bitmap.height));
+    assert_eq!(image.get((12, 12)), bitmap.get(12, 12));
+    assert_eq!(
+        decode_image(&image, 128).unwrap().text.as_deref(),
+        Some("png")
+    );
+    let pixels = |image: &qrcode::qr_code::Matrix<u8>| {
+        let (width, height) = image.size();
+        (0..height)
+            .map(|y| (0..width).map(|x| image.get((x, y))).collect())
+            .collect::<Vec<Vec<u8>>>()
+    };
+    // 3x5 RGBA, rows filtered None, Sub, Up, Average and Paeth, every other
+    // pixel at alpha 100; written by zlib with fixed Huffman codes.
+    let rgba = [
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x05, 0x08, 0x06, 0x00, 0x00, 0x00, 0x80,
+        0x71, 0x56, 0xa2, 0x00, 0x00, 0x00, 0x41, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0x60,
+        0x60, 0x60, 0x48, 0x09, 0x60, 0x60, 0xf8, 0xbf, 0x00, 0x48, 0x33, 0x32, 0x18, 0x31, 0xfc,
+        0x0f, 0x60, 0x90, 0x4b, 0x05, 0xe2, 0xd9, 0x4c, 0x40, 0x4e, 0x2a, 0x83, 0x91, 0xdc, 0x6c,
+        0x06, 0x23, 0x9b, 0x54, 0x66, 0x86, 0x14, 0x86, 0xb3, 0x1a, 0x92, 0x36, 0xa9, 0x1a, 0x92,
+        0xde, 0xb3, 0x59, 0xc0, 0x32, 0x40, 0x25, 0x0c, 0x0c, 0x36, 0xa9, 0x00, 0xb5, 0xca, 0x0d,
+        0x4f, 0x7c, 0x97, 0x32, 0xae, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42,
+        0x60, 0x82,
+    ];
+    assert_eq!(
+        pixels(&png::read(&rgba).unwrap()),
+        [
+            [155, 24, 174],
+            [29, 177, 84],
+            [178, 89, 202],
+            [88, 203, 156],
+            [201, 155, 230]
+        ]
+    );
+    // 6x5 with a 2-bit palette of black, red, half transparent blue and
+    // white, and a tEXt chunk.
+    let palette = [
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x05, 0x02, 0x03, 0x00, 0x00, 0x00, 0x1b,
+        0x36, 0x75, 0x75, 0x00, 0x00, 0x00, 0x0c, 0x50, 0x4c, 0x54, 0x45, 0x00, 0x00, 0x00, 0xff,
+        0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xa2, 0x1d, 0x1b, 0x3e, 0x00, 0x00, 0x00,
+        0x03, 0x74, 0x52, 0x4e, 0x53, 0xff, 0xff, 0x80, 0x3a, 0x72, 0x8e, 0x61, 0x00, 0x00, 0x00,
+        0x0c, 0x74, 0x45, 0x58, 0x74, 0x43, 0x6f, 0x6d, 0x6d, 0x65, 0x6e, 0x74, 0x00, 0x74, 0x65,
+        0x73, 0x74, 0x57, 0x61, 0x2b, 0xe9, 0x00, 0x00, 0x00, 0x17, 0x49, 0x44, 0x41, 0x54, 0x78,
+        0xda, 0x63, 0x90, 0x16, 0x60, 0xdc, 0xf8, 0x9f, 0x29, 0x2b, 0x81, 0x79, 0x49, 0x00, 0x4b,
+        0xd6, 0x57, 0x00, 0x20, 0x53, 0x05, 0x03, 0xf4, 0xe0, 0x53, 0x34, 0x00, 0x00, 0x00, 0x00,
+        0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+    let rows = pixels(&png::read(&palette).unwrap());
+    assert_eq!(rows[0], [0, 76, 142, 255, 0, 76]);
+    assert_eq!(rows[4], rows[0]);
+    assert_eq!(rows[3], [142, 255, 0, 76, 142, 255]);
+    // Dynamic Huffman block.
+    let compressed = [
+        0x78, 0xda, 0x8d, 0xca, 0xc9, 0x01, 0x80, 0x30, 0x08, 0x05, 0xd1, 0x56, 0x7e, 0x05, 0xf6,
+        0x14, 0x35, 0x1a, 0x5c, 0x40, 0x21, 0x71, 0xab, 0x5e, 0xbc, 0x78, 0xce, 0xbb, 0xce, 0xe4,
+        0x14, 0xb1, 0x17, 0xea, 0x66, 0xb4, 0x2a, 0x27, 0x63, 0x90, 0x0b, 0x53, 0x59, 0x37, 0x83,
+        0x1c, 0x51, 0x91, 0x3d, 0x2f, 0xe1, 0xb9, 0xd1, 0xcb, 0xd8, 0x20, 0xfe, 0xf2, 0x27, 0x38,
+        0x71, 0x44, 0xc4, 0xcc, 0x66, 0xa6, 0x9a, 0x52, 0xcd, 0xf3, 0x02, 0x57, 0x55, 0x2e, 0x58,
+    ];
+    let text: &[u8] = b"the quick brown fox jumps over the lazy dog. \
+          eeeeeeeeeetttttaaaaooooiiinnnsssrrhheeeeeeeeeetttttaaaaooooiiinnnsssrrhh";
+    assert_eq!(zlib_decompress(&compressed, text.len()).unwrap(), text);
+    assert_eq!(
+        zlib_decompress(&compressed, text.len() - 1).err(),
+        Some(Error::InvalidImage("deflate output longer than expected"))
+    );
+    let mut corrupt = palette.to_vec();
+    corrupt[30] ^= 1;
+    assert_eq!(
+        png::read(&corrupt).err(),
+        Some(Error::InvalidImage("PNG chunk checksum mismatch"))
+    );
+    assert!(png::read(&palette[..100]).is_err());
+    assert!(png::read(b"P5 1 1 255 x").is_err());
+    // Unfiltered images from an IHDR, a tRNS body and raw image data.
+    let build = |width: u32, depth: u8, color_type: u8, transparency: &[u8], raw: &[u8]| {
+        let mut header = width.to_be_bytes().to_vec();
+        header.extend_from_slice(&[0, 0, 0, 1, depth, color_type, 0, 0, 0]);
+        let mut idat = vec![0x78, 0x01, 0x01];
+        idat.extend_from_slice(&(raw.len() as u16).to_le_bytes());
+        idat.extend_from_slice(&(!(raw.len() as u16)).to_le_bytes());
+        idat.extend_from_slice(raw);
+        idat.extend_from_slice(&qrcode::bitmap::adler32(raw).to_be_bytes());
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+        for (name, body) in [
+            (b"IHDR", &header[..]),
+            (b"tRNS", transparency),
+            (b"IDAT", &idat[..]),
+            (b"IEND", &[]),
+        ] {
+            png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            let chunk = [&name[..], body].concat();
+            png.extend_from_slice(&chunk);
+            png.extend_from_slice(&qrcode::bitmap::crc32(&chunk).to_be_bytes());
+        }
+        png
+    };
+    let gray = build(2, 8, 0, &[0, 0], &[0, 0, 128]);
+    assert_eq!(pixels(&png::read(&gray).unwrap()), [[255, 128]]);
+    // The key compares all 16 bits, not the high byte only.
+    let deep = build(2, 16, 0, &[0, 1], &[0, 0, 0, 0, 1]);
+    assert_eq!(pixels(&png::read(&deep).unwrap()), [[0, 255]]);
+    let rgb = [0, 0, 0, 0, 255, 0, 0];
+    let keyed = build(2, 8, 2, &[0, 255, 0, 0, 0, 0], &rgb);
+    assert_eq!(pixels(&png::read(&keyed).unwrap()), [[0, 255]]);
+    let opaque = build(2, 8, 2, &[0, 0, 0, 255, 0, 0], &rgb);
+    assert_eq!(pixels(&png::read(&opaque).unwrap()), [[0, 76]]);
+    assert_eq!(
+        png::read(&build(2, 8, 0, &[], &[0, 0, 128, 7])).err(),
+        Some(Error::InvalidImage("deflate output longer than expected"))
+    );
+    let mut huge = build(u32::MAX, 16, 6, &[], &[]);
+    huge[20..24].copy_from_slice(&u32::MAX.to_be_bytes());
+    let crc = qrcode::bitmap::crc32(&huge[12..29]);
+    huge[29..33].copy_from_slice(&crc.to_be_bytes());
+    assert_eq!(
+        png::read(&huge).err(),
+        Some(Error::InvalidImage("image dimensions overflow"))
+    );
+}
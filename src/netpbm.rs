@@ -0,0 +1,244 @@
+use crate::bitmap::Bitmap;
+use crate::error::Error;
+use crate::qr_code::Matrix;
+use crate::render::Symbol;
+
+fn header(magic: &str, bitmap: &Bitmap, maxval: Option<u32>) -> Vec<u8> {
+    let mut header = format!("{}\n{} {}\n", magic, bitmap.width, bitmap.height);
+    if let Some(maxval) = maxval {
+        header += &format!("{}\n", maxval);
+    }
+    header.into_bytes()
+}
+
+fn check_scale(scale: u32) -> Result<(), Error> {
+    if scale == 0 {
+        return Err(Error::InvalidOption("scale must be at least 1"));
+    }
+    Ok(())
+}
+
+/// Plain text samples, at most 70 characters per line as the format asks.
+fn plain(samples: impl Iterator<Item = String>) -> Vec<u8> {
+    let mut text = String::new();
+    let mut line = 0;
+    for sample in samples {
+        if line + sample.len() + 1 > 70 {
+            text.push('\n');
+            line = 0;
+        } else if line > 0 {
+            text.push(' ');
+            line += 1;
+        }
+        line += sample.len();
+        text += &sample;
+    }
+    text.push('\n');
+    text.into_bytes()
+}
+
+/// Bitmap (`P1` plain or `P4` raw) with `scale` pixels per module, `1`
+/// for dark pixels.
+pub fn to_pbm(symbol: &dyn Symbol, scale: u32, raw: bool) -> Result<Vec<u8>, Error> {
+    check_scale(scale)?;
+    let bitmap = Bitmap::from_symbol(symbol, scale);
+    if !raw {
+        let mut pbm = header("P1", &bitmap, None);
+        pbm.extend(plain(
+            bitmap
+                .pixels
+                .iter()
+                .map(|pixel| ((*pixel == 0) as u8).to_string()),
+        ));
+        return Ok(pbm);
+    }
+    let mut pbm = header("P4", &bitmap, None);
+    for row in bitmap.pixels.chunks(bitmap.width as usize) {
+        for byte in row.chunks(8) {
+            pbm.push(
+                byte.iter()
+                    .enumerate()
+                    .filter(|(_, pixel)| **pixel == 0)
+                    .fold(0, |value, (i, _)| value | 0x80 >> i),
+            );
+        }
+    }
+    Ok(pbm)
+}
+
+/// Graymap (`P2` plain or `P5` raw) with a maximum value of 255.
+pub fn to_pgm(symbol: &dyn Symbol, scale: u32, raw: bool) -> Result<Vec<u8>, Error> {
+    check_scale(scale)?;
+    let bitmap = Bitmap::from_symbol(symbol, scale);
+    let mut pgm = header(if raw { "P5" } else { "P2" }, &bitmap, Some(255));
+    if raw {
+        pgm.extend_from_slice(&bitmap.pixels);
+    } else {
+        pgm.extend(plain(bitmap.pixels.iter().map(|pixel| pixel.to_string())));
+    }
+    Ok(pgm)
+}
+
+/// Pixmap (`P3` plain or `P6` raw) painting dark and light modules with
+/// the given RGB colors.
+pub fn to_ppm(
+    symbol: &dyn Symbol,
+    scale: u32,
+    raw: bool,
+    dark: [u8; 3],
+    light: [u8; 3],
+) -> Result<Vec<u8>, Error> {
+    check_scale(scale)?;
+    let bitmap = Bitmap::from_symbol(symbol, scale);
+    let mut ppm = header(if raw { "P6" } else { "P3" }, &bitmap, Some(255));
+    let samples = bitmap
+        .pixels
+        .iter()
+        .flat_map(|pixel| if *pixel == 0 { dark } else { light });
+    if raw {
+        ppm.extend(samples);
+    } else {
+        ppm.extend(plain(samples.map(|sample| sample.to_string())));
+    }
+    Ok(ppm)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.data.get(self.position) {
+            match byte {
+                b'#' => {
+                    while self
+                        .data
+                        .get(self.position)
+                        .is_some_and(|byte| *byte != b'\n')
+                    {
+                        self.position += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() => self.position += 1,
+                _ => break,
+            }
+        }
+    }
+    fn number(&mut self) -> Result<u32, Error> {
+        self.skip_whitespace();
+        let start = self.position;
+        while self.data.get(self.position).is_some_and(u8::is_ascii_digit) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.position])
+            .unwrap()
+            .parse()
+            .map_err(|_| Error::InvalidImage("expected a number"))
+    }
+    /// Plain PBM samples are single digits that need no separator.
+    fn bit(&mut self) -> Result<u32, Error> {
+        self.skip_whitespace();
+        match self.data.get(self.position) {
+            Some(b'0') | Some(b'1') => {
+                self.position += 1;
+                Ok((self.data[self.position - 1] - b'0') as u32)
+            }
+            _ => Err(Error::InvalidImage("expected 0 or 1")),
+        }
+    }
+}
+
+/// Reads a `P1`, `P2`, `P4` or `P5` image into a grayscale matrix, `0`
+/// black to `255` white, whatever the maximum value of the file.
+pub fn read(data: &[u8]) -> Result<Matrix<u8>, Error> {
+    let magic = data
+        .get(..2)
+        .ok_or(Error::InvalidImage("missing magic number"))?;
+    let mut reader = Reader { data, position: 2 };
+    let width = reader.number()?;
+    let height = reader.number()?;
+    let maxval = match magic {
+        b"P1" | b"P4" => 1,
+        b"P2" | b"P5" => reader.number()?,
+        _ => return Err(Error::InvalidImage("only PBM and PGM can be read")),
+    };
+    if width == 0 || height == 0 || maxval == 0 || maxval > 65535 {
+        return Err(Error::InvalidImage(
+            "dimensions or maximum value out of range",
+        ));
+    }
+    // A single whitespace character separates the header from raw data.
+    let raster_start = reader.position + 1;
+    let row_bytes = width.div_ceil(8) as usize;
+    let sample_bytes = if maxval > 255 { 2 } else { 1 };
+    // Plain samples take at least one byte each, raw ones exactly their
+    // size; checked before the image is allocated.
+    let raster_bytes = match magic {
+        b"P4" => row_bytes.checked_mul(height as usize),
+        b"P5" => (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|samples| samples.checked_mul(sample_bytes)),
+        _ => (width as usize).checked_mul(height as usize),
+    };
+    let available = match magic {
+        b"P4" | b"P5" => data.len().saturating_sub(raster_start),
+        _ => data.len() - reader.position,
+    };
+    if raster_bytes.is_none_or(|bytes| bytes > available) {
+        return Err(Error::InvalidImage("truncated raster"));
+    }
+    let mut image: Matrix<u8> = Matrix::create(width, height, &0);
+    for y in 0..height {
+        for x in 0..width {
+            let value = match magic {
+                b"P1" => reader.bit()?,
+                b"P2" => reader.number()?,
+                b"P4" => {
+                    let index = raster_start + y as usize * row_bytes + x as usize / 8;
+                    let byte = data
+                        .get(index)
+                        .ok_or(Error::InvalidImage("truncated raster"))?;
+                    (byte >> (7 - x % 8) & 1) as u32
+                }
+                _ => {
+                    let index =
+                        raster_start + (y as usize * width as usize + x as usize) * sample_bytes;
+                    let sample = data
+                        .get(index..index + sample_bytes)
+                        .ok_or(Error::InvalidImage("truncated raster"))?;
+                    sample
+                        .iter()
+                        .fold(0, |value, byte| value << 8 | *byte as u32)
+                }
+            };
+            if value > maxval {
+                return Err(Error::InvalidImage("sample above the maximum value"));
+            }
+            // PBM stores ink, `1` is black.
+            let gray = if magic == b"P1" || magic == b"P4" {
+                (1 - value) * 255
+            } else {
+                value * 255 / maxval
+            };
+            image.put((x, y), gray as u8);
+        }
+    }
+    Ok(image)
+}
+
+/// Samples the center of every `scale` x `scale` cell of a grayscale image
+/// into modules, `0` dark and `1` light.
+pub fn to_modules(image: &Matrix<u8>, scale: u32) -> Result<Matrix<u8>, Error> {
+    check_scale(scale)?;
+    let (width, height) = image.size();
+    let mut modules: Matrix<u8> = Matrix::create(width / scale, height / scale, &1);
+    for y in 0..height / scale {
+        for x in 0..width / scale {
+            let gray = image.get((x * scale + scale / 2, y * scale + scale / 2));
+            modules.put((x, y), (gray >= 128) as u8);
+        }
+    }
+    Ok(modules)
+}
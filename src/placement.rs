@@ -0,0 +1,114 @@
+use crate::config::ModuleKind;
+use crate::qr_code::QRcode;
+use crate::render::Symbol;
+
+/// Fill of a placed module: hue per block, saturation for data against
+/// error correction, a small hue step between neighbouring codewords and
+/// lightness for the final dark/light value.
+fn module_color(qrcode: &QRcode, kind: &ModuleKind, dark: bool) -> String {
+    let lightness = if dark { 30 } else { 78 };
+    let (codeword, saturation) = match kind {
+        ModuleKind::Data { codeword, .. } => (*codeword as usize, 75),
+        ModuleKind::ErrorCorrection { codeword, .. } => (*codeword as usize, 35),
+        _ => return format!("hsl(0,0%,{}%)", lightness),
+    };
+    let hue = (qrcode.rs.codeword_block(codeword) * 137 + (codeword % 2) * 18) % 360;
+    format!("hsl({},{}%,{}%)", hue, saturation, lightness)
+}
+
+/// Square of module `(x, y)`, shifted by the quiet zone.
+fn rect(qrcode: &QRcode, x: u32, y: u32, module_size: u32, fill: &str) -> String {
+    format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{3}\" height=\"{3}\" fill=\"{}\"",
+        (x + qrcode.quiet_zone()) * module_size,
+        (y + qrcode.quiet_zone()) * module_size,
+        fill,
+        module_size
+    )
+}
+
+/// Function patterns in black and white, drawn before any data.
+fn function_patterns(qrcode: &QRcode, module_size: u32) -> String {
+    let modules = qrcode.modules();
+    let kinds = qrcode.module_kinds();
+    let (size_x, size_y) = modules.size();
+    let mut svg = String::new();
+    for y in 0..size_y {
+        for x in 0..size_x {
+            let placed = matches!(
+                kinds.get((x, y)),
+                ModuleKind::Data { .. }
+                    | ModuleKind::ErrorCorrection { .. }
+                    | ModuleKind::Remainder
+            );
+            if !placed && modules.get((x, y)) == 0 {
+                svg += &rect(qrcode, x, y, module_size, "#000000");
+                svg += "/>";
+            }
+        }
+    }
+    svg
+}
+
+/// Placed modules with the index of the codeword each belongs to; the
+/// remainder bits count as one extra codeword after the last.
+fn placed_modules(qrcode: &QRcode, module_size: u32) -> Vec<(usize, String)> {
+    let modules = qrcode.modules();
+    let kinds = qrcode.module_kinds();
+    qrcode
+        .placement()
+        .iter()
+        .enumerate()
+        .map(|(position, (x, y))| {
+            let kind = kinds.get((*x, *y));
+            let fill = module_color(qrcode, &kind, modules.get((*x, *y)) == 0);
+            (position / 8, rect(qrcode, *x, *y, module_size, &fill))
+        })
+        .collect()
+}
+
+fn svg_document(qrcode: &QRcode, module_size: u32, body: &str) -> String {
+    let (size_x, size_y) = qrcode.rendered_size();
+    let (width, height) = (size_x * module_size, size_y * module_size);
+    format!(
+        concat!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" ",
+            "viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">",
+            "<rect width=\"{0}\" height=\"{1}\" fill=\"#ffffff\"/>{2}</svg>"
+        ),
+        width, height, body
+    )
+}
+
+/// Animated SVG (SMIL) of the placement order recorded by
+/// `push_data_strip`: function patterns are shown at once, then every
+/// codeword appears `seconds_per_codeword` after the previous one. The
+/// symbol has to be rendered first.
+pub fn to_animated_svg(qrcode: &QRcode, module_size: u32, seconds_per_codeword: f32) -> String {
+    let mut body = function_patterns(qrcode, module_size);
+    for (codeword, module) in placed_modules(qrcode, module_size) {
+        body += &format!(
+            "{} visibility=\"hidden\"><set attributeName=\"visibility\" to=\"visible\" begin=\"{}s\" fill=\"freeze\"/></rect>",
+            module,
+            codeword as f32 * seconds_per_codeword
+        );
+    }
+    svg_document(qrcode, module_size, &body)
+}
+
+/// One still SVG per codeword, frame `i` showing the function patterns and
+/// codewords `0..=i`; the last frame adds the remainder bits, if any.
+/// Frames are built on demand, each extending the body of the previous one,
+/// so only the frame being read is held in memory.
+pub fn to_frames(qrcode: &QRcode, module_size: u32) -> impl Iterator<Item = String> + '_ {
+    let mut body = function_patterns(qrcode, module_size);
+    let mut modules = placed_modules(qrcode, module_size).into_iter().peekable();
+    std::iter::from_fn(move || {
+        let frame = modules.peek()?.0;
+        while let Some((_, module)) = modules.next_if(|(codeword, _)| *codeword == frame) {
+            body += &module;
+            body += "/>";
+        }
+        Some(svg_document(qrcode, module_size, &body))
+    })
+}
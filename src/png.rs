@@ -0,0 +1,267 @@
+use crate::bitmap::crc32;
+use crate::color::Rgba;
+use crate::error::Error;
+use crate::inflate::zlib_decompress;
+use crate::qr_code::Matrix;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+struct Header {
+    width: u32,
+    height: u32,
+    depth: u8,
+    color_type: u8,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Header, Error> {
+        if data.len() != 13 {
+            return Err(Error::InvalidImage("IHDR is not 13 bytes"));
+        }
+        let header = Header {
+            width: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            height: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            depth: data[8],
+            color_type: data[9],
+        };
+        let depths: &[u8] = match header.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => return Err(Error::InvalidImage("unknown PNG color type")),
+        };
+        if !depths.contains(&header.depth) {
+            return Err(Error::InvalidImage(
+                "bit depth not allowed for the color type",
+            ));
+        }
+        if header.width == 0 || header.height == 0 || data[10] != 0 || data[11] != 0 {
+            return Err(Error::InvalidImage("dimensions or methods out of range"));
+        }
+        if data[12] != 0 {
+            return Err(Error::Unsupported("interlaced PNG"));
+        }
+        let (width, height) = (header.width as usize, header.height as usize);
+        let raw_length = width
+            .checked_mul(header.bits_per_pixel())
+            .map(|bits| bits.div_ceil(8) + 1)
+            .and_then(|line| line.checked_mul(height));
+        if raw_length.is_none() || width.checked_mul(height).is_none() {
+            return Err(Error::InvalidImage("image dimensions overflow"));
+        }
+        Ok(header)
+    }
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.depth as usize
+    }
+    /// Bytes of one row without its filter type byte.
+    fn stride(&self) -> usize {
+        (self.width as usize * self.bits_per_pixel()).div_ceil(8)
+    }
+    /// Bytes of the decompressed image data, a filter type byte per row.
+    fn raw_length(&self) -> usize {
+        (self.stride() + 1) * self.height as usize
+    }
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let (a, b, c) = (
+        (estimate - left as i16).abs(),
+        (estimate - up as i16).abs(),
+        (estimate - up_left as i16).abs(),
+    );
+    if a <= b && a <= c {
+        left
+    } else if b <= c {
+        up
+    } else {
+        up_left
+    }
+}
+
+/// Rows with their filters undone, `stride` bytes each. Bytes left of
+/// the row and above the first one count as zero.
+fn unfilter(raw: &[u8], header: &Header) -> Result<Vec<u8>, Error> {
+    let stride = header.stride();
+    let height = header.height as usize;
+    if raw.len() < header.raw_length() {
+        return Err(Error::InvalidImage("image data shorter than the image"));
+    }
+    let distance = header.bits_per_pixel().div_ceil(8);
+    let mut rows = vec![0u8; stride * height];
+    for y in 0..height {
+        let filter = raw[y * (stride + 1)];
+        let line = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        for x in 0..stride {
+            let left = if x >= distance {
+                rows[y * stride + x - distance]
+            } else {
+                0
+            };
+            let up = if y > 0 { rows[(y - 1) * stride + x] } else { 0 };
+            let up_left = if x >= distance && y > 0 {
+                rows[(y - 1) * stride + x - distance]
+            } else {
+                0
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(Error::InvalidImage("unknown PNG filter type")),
+            };
+            rows[y * stride + x] = line[x].wrapping_add(predictor);
+        }
+    }
+    Ok(rows)
+}
+
+/// Luma of `color` painted over white, `0` black to `255` white.
+fn gray(color: Rgba) -> u8 {
+    let color = color.over(&Rgba::WHITE);
+    let luma = 299 * color.red as u32 + 587 * color.green as u32 + 114 * color.blue as u32;
+    ((luma + 500) / 1000) as u8
+}
+
+/// Reads a PNG image into a grayscale matrix, `0` black to `255` white.
+/// Every color type and bit depth is accepted; color becomes its luma and
+/// transparent pixels, `tRNS` palette alpha and gray or RGB color keys
+/// included, are painted over white. Interlaced images are not supported.
+pub fn read(data: &[u8]) -> Result<Matrix<u8>, Error> {
+    if data.get(..8) != Some(&SIGNATURE[..]) {
+        return Err(Error::InvalidImage("missing PNG signature"));
+    }
+    let mut position = 8;
+    let mut header: Option<Header> = None;
+    let mut palette: Vec<Rgba> = Vec::new();
+    let mut transparency: Vec<u8> = Vec::new();
+    let mut compressed: Vec<u8> = Vec::new();
+    loop {
+        let length = data
+            .get(position..position + 4)
+            .ok_or(Error::InvalidImage("PNG ends before IEND"))?;
+        let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+        let end = position + 8 + length;
+        let chunk = data
+            .get(position + 4..end + 4)
+            .ok_or(Error::InvalidImage("PNG chunk runs past the end"))?;
+        let (name, body, crc) = (&chunk[..4], &chunk[4..4 + length], &chunk[4 + length..]);
+        if crc32(&chunk[..4 + length]) != u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+            return Err(Error::InvalidImage("PNG chunk checksum mismatch"));
+        }
+        position = end + 4;
+        if header.is_none() && name != b"IHDR" {
+            return Err(Error::InvalidImage("PNG does not start with IHDR"));
+        }
+        match name {
+            b"IHDR" => header = Some(Header::parse(body)?),
+            b"PLTE" => {
+                palette = body
+                    .chunks_exact(3)
+                    .map(|rgb| Rgba::rgb(rgb[0], rgb[1], rgb[2]))
+                    .collect()
+            }
+            b"tRNS" => transparency = body.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            // Lowercase first letter: ancillary, safe to ignore.
+            _ if name[0].is_ascii_lowercase() => {}
+            _ => return Err(Error::Unsupported("unknown critical PNG chunk")),
+        }
+    }
+    let header = header.unwrap();
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(Error::InvalidImage("palette image without PLTE"));
+    }
+    for (color, alpha) in palette.iter_mut().zip(&transparency) {
+        color.alpha = *alpha;
+    }
+    // Gray or RGB samples, at the image bit depth, drawn fully transparent.
+    let color_key: Option<Vec<u16>> = match header.color_type {
+        0 | 2 if transparency.len() >= 2 * header.channels() => Some(
+            transparency
+                .chunks_exact(2)
+                .take(header.channels())
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect(),
+        ),
+        _ => None,
+    };
+    let rows = unfilter(&zlib_decompress(&compressed, header.raw_length())?, &header)?;
+    let stride = header.stride();
+    let depth = header.depth as usize;
+    let channels = header.channels();
+    // Value of `channel` of pixel `x` in row `y` at the image bit depth.
+    let raw = |x: usize, y: usize, channel: usize| -> u16 {
+        let row = &rows[y * stride..(y + 1) * stride];
+        let index = x * channels + channel;
+        match depth {
+            16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+            8 => row[index] as u16,
+            _ => {
+                let bit = index * depth;
+                ((row[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1) as u8) as u16
+            }
+        }
+    };
+    // The raw value for palette indices, scaled to 0..255 otherwise.
+    let sample = |x: usize, y: usize, channel: usize| -> u8 {
+        let value = raw(x, y, channel);
+        match depth {
+            16 => (value >> 8) as u8,
+            _ if header.color_type == 3 => value as u8,
+            _ => (value as u32 * 255 / ((1 << depth) - 1)) as u8,
+        }
+    };
+    let keyed = |x: usize, y: usize| {
+        color_key.as_ref().is_some_and(|key| {
+            key.iter()
+                .enumerate()
+                .all(|(channel, value)| raw(x, y, channel) == *value)
+        })
+    };
+    let mut image: Matrix<u8> = Matrix::create(header.width, header.height, &0);
+    for y in 0..header.height as usize {
+        for x in 0..header.width as usize {
+            let color = match header.color_type {
+                0 => {
+                    let value = sample(x, y, 0);
+                    Rgba::rgb(value, value, value)
+                }
+                2 => Rgba::rgb(sample(x, y, 0), sample(x, y, 1), sample(x, y, 2)),
+                3 => *palette
+                    .get(sample(x, y, 0) as usize)
+                    .ok_or(Error::InvalidImage("palette index out of range"))?,
+                4 => {
+                    let value = sample(x, y, 0);
+                    Rgba {
+                        alpha: sample(x, y, 1),
+                        ..Rgba::rgb(value, value, value)
+                    }
+                }
+                _ => Rgba {
+                    alpha: sample(x, y, 3),
+                    ..Rgba::rgb(sample(x, y, 0), sample(x, y, 1), sample(x, y, 2))
+                },
+            };
+            let color = if keyed(x, y) {
+                Rgba { alpha: 0, ..color }
+            } else {
+                color
+            };
+            image.put((x as u32, y as u32), gray(color));
+        }
+    }
+    Ok(image)
+}
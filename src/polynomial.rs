@@ -1,6 +1,6 @@
-#[path = "config.rs"]
-mod config;
-use config::EntityPolynomial;
+use crate::config;
+use crate::config::EntityPolynomial;
+use std::sync::OnceLock;
 use std::vec::Vec;
 use std::{fmt, vec};
 
@@ -11,15 +11,16 @@ pub struct Polynomial {
 impl fmt::Debug for Polynomial {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), std::fmt::Error> {
         for _polynomial in &self.data {
-            println!(
+            writeln!(
+                f,
                 "{:?}",
                 format!(
                     "|x:{}; alpha:{}; value:{}|",
                     _polynomial.0, _polynomial.1, _polynomial.2
                 )
-            )
+            )?
         }
-        write!(f, "")
+        Ok(())
     }
 }
 pub const BASE_POLYNOMIAL: fn(i16) -> Polynomial = |x| Polynomial::new(vec![(1, 0, 0), (0, x, 0)]);
@@ -58,11 +59,11 @@ impl Polynomial {
             let mut operand_poly: EntityPolynomial = (polynomial.0, 0, polynomial.2);
 
             if let Some(val) = _polynomial_map.find_polynomial(polynomial.0) {
-                val.1 = val.1 ^ (exponent_galois(polynomial.1 as u32) as i16);
+                val.1 ^= exponent_galois(polynomial.1 as u32) as i16;
                 continue;
             };
 
-            operand_poly.1 = 0 ^ (exponent_galois(polynomial.1 as u32) as i16);
+            operand_poly.1 = exponent_galois(polynomial.1 as u32) as i16;
             _polynomial_map.push(operand_poly)
         }
         _polynomial_map.data.reverse();
@@ -73,7 +74,7 @@ impl Polynomial {
             .collect::<Vec<EntityPolynomial>>();
 
         _polynomial_map.sort_by(|a, b| b.cmp(a));
-        return Polynomial::new(_polynomial_map);
+        Polynomial::new(_polynomial_map)
     }
     pub fn multiply_by_exp(&self, by_polynomial: Vec<EntityPolynomial>) -> Polynomial {
         let ziped_polynomial: Vec<Vec<EntityPolynomial>> = self
@@ -95,11 +96,11 @@ impl Polynomial {
             let mut operand_poly: EntityPolynomial = (polynomial.0, 0, polynomial.2);
 
             if let Some(val) = _polynomial_map.find_polynomial(polynomial.0) {
-                val.1 = val.1 ^ (exponent_galois(polynomial.1 as u32) as i16);
+                val.1 ^= exponent_galois(polynomial.1 as u32) as i16;
                 continue;
             };
 
-            operand_poly.1 = 0 ^ (exponent_galois(polynomial.1 as u32) as i16);
+            operand_poly.1 = exponent_galois(polynomial.1 as u32) as i16;
             _polynomial_map.push(operand_poly)
         }
         _polynomial_map.data.reverse();
@@ -110,7 +111,7 @@ impl Polynomial {
             .collect::<Vec<EntityPolynomial>>();
 
         _polynomial_map.sort_by(|a, b| b.cmp(a));
-        return Polynomial::new(_polynomial_map);
+        Polynomial::new(_polynomial_map)
     }
 }
 
@@ -121,128 +122,298 @@ fn reduce_galois_operator(operator: &mut u32) {
         }
     }
 }
-fn decrease_polynomial(polynomial_generator: Polynomial) -> Polynomial {
-    polynomial_generator.multiply_by_poly(Polynomial::new(vec![(-1, 0, 0)]))
-}
-
 pub fn generator_polynomial(number: i32) -> Polynomial {
     let mut tmp_polynomial: Polynomial = BASE_POLYNOMIAL(0);
     for i in 1..number {
         tmp_polynomial = tmp_polynomial.multiply_by_poly(BASE_POLYNOMIAL(i as i16));
     }
-    return tmp_polynomial;
+    tmp_polynomial
 }
-pub fn div_polynomial(dec_words: &mut Vec<i16>, error_correction: i16) -> Polynomial {
-    let mut polynomial_message: Vec<EntityPolynomial> =
-        generator_polynomial((dec_words.len() as i32) - 1)
-            .data
-            .iter_mut()
+pub fn div_polynomial(dec_words: &mut [i16], error_correction: i16) -> Polynomial {
+    let field = qr_galois_field();
+    let message: Vec<u32> = dec_words.iter().map(|word| *word as u32).collect();
+    let remainder = field.remainder(&message, error_correction as usize);
+    Polynomial::new(
+        remainder
+            .iter()
             .enumerate()
-            .map(|(i, exponent)| {
-                exponent.0 += error_correction;
-                //println!("{:?}", i);
-                exponent.2 = dec_words[i];
-                *exponent
+            .map(|(i, value)| {
+                (
+                    error_correction - 1 - i as i16,
+                    field.log(*value) as i16,
+                    *value as i16,
+                )
             })
-            .collect();
-    let exponent_diff: i16 = polynomial_message[0].0 - error_correction;
+            .collect(),
+    )
+}
+pub fn exponent_galois(mut exponent: u32) -> u32 {
+    reduce_galois_operator(&mut exponent);
+    qr_galois_field().exp(exponent)
+}
+pub fn reverse_exponent_galois(mut target: u32) -> u32 {
+    reduce_galois_operator(&mut target);
+    qr_galois_field().log(target)
+}
 
-    let polynomial_generator_data: Vec<EntityPolynomial> =
-        generator_polynomial(error_correction as i32)
-            .data
-            .iter_mut()
-            .map(|x| {
-                x.0 = x.0 + exponent_diff;
-                *x
-            })
-            .collect();
-    let mut polynomial_generator = Polynomial::new(polynomial_generator_data);
-    let mut buffer = 0;
-    let mut u = 0;
-    while u < (exponent_diff + buffer + (if dec_words.len() < 19 { 1 } else { 0 })) {
-        u += 1;
-        // MULTI
-        let lead_exponent = reverse_exponent_galois(polynomial_message[0].2 as u32) as i16;
-        let mut tmp_polynomial_generator: Vec<EntityPolynomial> = polynomial_generator
-            .multiply_by_exp(vec![(0, lead_exponent, 0)])
-            .data
-            .iter_mut()
-            .map(|x| {
-                x.2 = exponent_galois(x.1 as u32) as i16;
-                *x
-            })
-            .collect();
+/// GF(256) with the QR primitive polynomial `x^8 + x^4 + x^3 + x^2 + 1`,
+/// shared by `exponent_galois` and `reverse_exponent_galois`.
+pub fn qr_galois_field() -> &'static GaloisField {
+    static FIELD: OnceLock<GaloisField> = OnceLock::new();
+    FIELD.get_or_init(|| GaloisField::new(config::MODULO_BYTE_WISE, 256, 0))
+}
 
-        //XOR
-        let mapping = |base: &mut Vec<EntityPolynomial>,
-                       side: &dyn Fn(i16, usize) -> i16|
-         -> Vec<EntityPolynomial> {
-            base.iter_mut()
-                .enumerate()
-                .map(|(i, x)| {
-                    x.2 = side(x.2, i);
-                    *x
-                })
-                .collect()
-        };
-        if polynomial_message.len() == tmp_polynomial_generator.len() {
-            buffer += 1;
+/// Binary extension field GF(size) built from a primitive polynomial.
+///
+/// `generator_base` is the exponent of the first root of the Reed-Solomon
+/// generator polynomial: QR uses `(x - a^0)(x - a^1)...`, Aztec starts at `a^1`.
+pub struct GaloisField {
+    pub size: u32,
+    pub primitive: u32,
+    pub generator_base: u32,
+    exp_table: Vec<u32>,
+    log_table: Vec<u32>,
+}
+
+impl GaloisField {
+    pub fn new(primitive: u32, size: u32, generator_base: u32) -> GaloisField {
+        let mut exp_table: Vec<u32> = vec![0; size as usize];
+        let mut log_table: Vec<u32> = vec![0; size as usize];
+        let mut value: u32 = 1;
+        for i in 0..size {
+            exp_table[i as usize] = value;
+            value <<= 1;
+            if value >= size {
+                value ^= primitive;
+                value &= size - 1;
+            }
+        }
+        for i in 0..(size - 1) {
+            log_table[exp_table[i as usize] as usize] = i;
+        }
+        GaloisField {
+            size,
+            primitive,
+            generator_base,
+            exp_table,
+            log_table,
+        }
+    }
+    /// `a^exponent`, the exponent taken modulo the multiplicative order.
+    pub fn exp(&self, exponent: u32) -> u32 {
+        self.exp_table[(exponent % (self.size - 1)) as usize]
+    }
+    /// Discrete logarithm of a non-zero element, `0` for `0`.
+    pub fn log(&self, value: u32) -> u32 {
+        self.log_table[value as usize]
+    }
+    pub fn multiply(&self, a: u32, b: u32) -> u32 {
+        if a == 0 || b == 0 {
+            return 0;
         }
-        let by_right: &dyn Fn(i16, usize) -> i16 = &|item, index| {
-            let mut value: i16 = 0;
-            if tmp_polynomial_generator.get(index).is_some() {
-                value = tmp_polynomial_generator.get(index).unwrap().2
+        self.exp(self.log(a) + self.log(b))
+    }
+    pub fn inverse(&self, a: u32) -> u32 {
+        if a == 0 {
+            panic!("zero has no inverse in a Galois field");
+        }
+        self.exp(self.size - 1 - self.log(a))
+    }
+    /// Coefficients (highest degree first) of the monic generator polynomial
+    /// with `degree` consecutive roots starting at `a^generator_base`.
+    pub fn generator(&self, degree: usize) -> Vec<u32> {
+        let mut generator: Vec<u32> = vec![1];
+        for i in 0..degree {
+            let root = self.exp(self.generator_base + i as u32);
+            let mut next: Vec<u32> = vec![0; generator.len() + 1];
+            for (j, coefficient) in generator.iter().enumerate() {
+                next[j] ^= *coefficient;
+                next[j + 1] ^= self.multiply(*coefficient, root);
             }
-            item ^ value as i16
-        };
-        let by_left: &dyn Fn(i16, usize) -> i16 = &|item, index| {
-            let mut value: i16 = 0;
-            if polynomial_message.get(index).is_some() {
-                value = polynomial_message.get(index).unwrap().2
+            generator = next;
+        }
+        generator
+    }
+    /// Reed-Solomon check words: the remainder of `data * x^ec_count`
+    /// divided by the generator polynomial.
+    pub fn remainder(&self, data: &[u32], ec_count: usize) -> Vec<u32> {
+        let generator = self.generator(ec_count);
+        let mut remainder: Vec<u32> = vec![0; ec_count];
+        for word in data {
+            let factor = word ^ remainder[0];
+            remainder.remove(0);
+            remainder.push(0);
+            if factor == 0 {
+                continue;
             }
-            value ^ item as i16
-        };
-        // fn x(item: i16, index: usize) -> i16 {
-        //     (item ^ tmp_polynomial_generator[index].2) as i16
-        // };
-        if polynomial_message.len() > tmp_polynomial_generator.len() {
-            polynomial_message = mapping(&mut polynomial_message, by_right)
-        } else {
-            polynomial_message = mapping(&mut tmp_polynomial_generator, by_left)
-        }
-        let mut x = 0;
-        while polynomial_message[0].2 == 0 {
-            polynomial_message.remove(0);
-            x += 1;
-        }
-        if x > 1 {
-            buffer -= 1;
-            polynomial_generator = decrease_polynomial(polynomial_generator);
-        }
-        polynomial_generator = decrease_polynomial(polynomial_generator);
-    }
-    return Polynomial::new(polynomial_message);
-}
-pub fn exponent_galois(mut exponent: u32) -> u32 {
-    reduce_galois_operator(&mut exponent);
-    if exponent == 8 {
-        return u32::pow(2, exponent) ^ config::MODULO_BYTE_WISE;
-    } else if exponent > 8 {
-        let prev_power = exponent_galois(exponent - 1) * 2;
-        return if prev_power >= 255 {
-            prev_power ^ config::MODULO_BYTE_WISE
-        } else {
-            prev_power
+            for (i, value) in remainder.iter_mut().enumerate() {
+                *value ^= self.multiply(generator[i + 1], factor);
+            }
+        }
+        remainder
+    }
+    /// Corrects a received `codeword` (data then `ec_count` check words,
+    /// highest degree first) in place with Berlekamp-Massey, Chien search
+    /// and Forney, returning the number of words repaired, or `None` when
+    /// there are more errors than the check words can locate.
+    pub fn correct(&self, codeword: &mut [u32], ec_count: usize) -> Option<usize> {
+        let order = self.size - 1;
+        let evaluate = |polynomial: &[u32], x: u32| {
+            polynomial
+                .iter()
+                .rev()
+                .fold(0, |sum, coefficient| self.multiply(sum, x) ^ coefficient)
         };
+        // Lowest degree first from here on.
+        let received: Vec<u32> = codeword.iter().rev().copied().collect();
+        let syndromes: Vec<u32> = (0..ec_count)
+            .map(|j| evaluate(&received, self.exp(self.generator_base + j as u32)))
+            .collect();
+        if syndromes.iter().all(|syndrome| *syndrome == 0) {
+            return Some(0);
+        }
+        let mut locator: Vec<u32> = vec![1];
+        let mut previous: Vec<u32> = vec![1];
+        let (mut errors, mut shift, mut last) = (0, 1, 1);
+        for n in 0..ec_count {
+            let discrepancy = (1..=errors.min(locator.len() - 1)).fold(syndromes[n], |sum, i| {
+                sum ^ self.multiply(locator[i], syndromes[n - i])
+            });
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+            let factor = self.multiply(discrepancy, self.inverse(last));
+            let mut next = locator.clone();
+            next.resize(next.len().max(previous.len() + shift), 0);
+            for (i, coefficient) in previous.iter().enumerate() {
+                next[i + shift] ^= self.multiply(factor, *coefficient);
+            }
+            if 2 * errors <= n {
+                previous = std::mem::replace(&mut locator, next);
+                errors = n + 1 - errors;
+                last = discrepancy;
+                shift = 1;
+            } else {
+                locator = next;
+                shift += 1;
+            }
+        }
+        if 2 * errors > ec_count {
+            return None;
+        }
+        let mut evaluator: Vec<u32> = vec![0; ec_count];
+        for (i, syndrome) in syndromes.iter().enumerate() {
+            for (j, coefficient) in locator.iter().enumerate().take(ec_count - i) {
+                evaluator[i + j] ^= self.multiply(*syndrome, *coefficient);
+            }
+        }
+        // Formal derivative: only odd powers survive in characteristic 2.
+        let derivative: Vec<u32> = (1..locator.len())
+            .map(|i| if i % 2 == 1 { locator[i] } else { 0 })
+            .collect();
+        let length = codeword.len();
+        let mut repaired = 0;
+        for power in 0..length as u32 {
+            let inverse = self.exp(order - power % order);
+            if evaluate(&locator, inverse) != 0 {
+                continue;
+            }
+            let scale = self.exp(power * ((order + 1 - self.generator_base % order) % order));
+            let denominator = evaluate(&derivative, inverse);
+            if denominator == 0 {
+                return None;
+            }
+            let magnitude = self.multiply(
+                scale,
+                self.multiply(evaluate(&evaluator, inverse), self.inverse(denominator)),
+            );
+            codeword[length - 1 - power as usize] ^= magnitude;
+            repaired += 1;
+        }
+        if repaired != errors {
+            return None;
+        }
+        Some(repaired)
     }
-    u32::pow(2, exponent)
 }
-pub fn reverse_exponent_galois(mut target: u32) -> u32 {
-    reduce_galois_operator(&mut target);
-    for i in 0u32..255 {
-        if target == exponent_galois(i) {
-            return i;
+
+/// Prime field GF(modulus) described by a primitive element, used by
+/// PDF417 error correction over GF(929) with `3` as generator.
+pub struct PrimeField {
+    pub modulus: u32,
+    pub primitive: u32,
+    pub generator_base: u32,
+    exp_table: Vec<u32>,
+    log_table: Vec<u32>,
+}
+
+impl PrimeField {
+    pub fn new(modulus: u32, primitive: u32, generator_base: u32) -> PrimeField {
+        let mut exp_table: Vec<u32> = vec![0; modulus as usize];
+        let mut log_table: Vec<u32> = vec![0; modulus as usize];
+        let mut value: u32 = 1;
+        for i in 0..modulus {
+            exp_table[i as usize] = value;
+            value = value * primitive % modulus;
         }
+        for i in 0..(modulus - 1) {
+            log_table[exp_table[i as usize] as usize] = i;
+        }
+        PrimeField {
+            modulus,
+            primitive,
+            generator_base,
+            exp_table,
+            log_table,
+        }
+    }
+    pub fn exp(&self, exponent: u32) -> u32 {
+        self.exp_table[(exponent % (self.modulus - 1)) as usize]
+    }
+    pub fn log(&self, value: u32) -> u32 {
+        self.log_table[value as usize]
+    }
+    pub fn add(&self, a: u32, b: u32) -> u32 {
+        (a + b) % self.modulus
+    }
+    pub fn subtract(&self, a: u32, b: u32) -> u32 {
+        (self.modulus + a - b) % self.modulus
+    }
+    pub fn multiply(&self, a: u32, b: u32) -> u32 {
+        a * b % self.modulus
+    }
+    /// Coefficients (highest degree first) of the monic polynomial with
+    /// roots `p^generator_base .. p^(generator_base + degree - 1)`.
+    pub fn generator(&self, degree: usize) -> Vec<u32> {
+        let mut generator: Vec<u32> = vec![1];
+        for i in 0..degree {
+            let root = self.exp(self.generator_base + i as u32);
+            let mut next: Vec<u32> = vec![0; generator.len() + 1];
+            for (j, coefficient) in generator.iter().enumerate() {
+                next[j] = self.add(next[j], *coefficient);
+                next[j + 1] = self.subtract(next[j + 1], self.multiply(*coefficient, root));
+            }
+            generator = next;
+        }
+        generator
+    }
+    /// Check words making `data * x^ec_count + check` divisible by the
+    /// generator: the negated remainder of the division.
+    pub fn remainder(&self, data: &[u32], ec_count: usize) -> Vec<u32> {
+        let generator = self.generator(ec_count);
+        let mut remainder: Vec<u32> = vec![0; ec_count];
+        for word in data {
+            let factor = self.add(*word, remainder[0]);
+            remainder.remove(0);
+            remainder.push(0);
+            for (i, value) in remainder.iter_mut().enumerate() {
+                *value = self.subtract(*value, self.multiply(generator[i + 1], factor));
+            }
+        }
+        remainder
+            .iter()
+            .map(|value| self.subtract(0, *value))
+            .collect()
     }
-    return 0;
 }
@@ -0,0 +1,220 @@
+use crate::contour;
+use crate::render::{number, padded_rows, Symbol};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    Millimetres(f32),
+    Points(f32),
+}
+
+impl Length {
+    pub fn points(&self) -> f32 {
+        match self {
+            Length::Millimetres(value) => value * 72.0 / 25.4,
+            Length::Points(value) => *value,
+        }
+    }
+}
+
+/// Process color, every component from `0.0` to `1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cmyk {
+    pub cyan: f32,
+    pub magenta: f32,
+    pub yellow: f32,
+    pub black: f32,
+}
+
+impl Cmyk {
+    pub const BLACK: Cmyk = Cmyk {
+        cyan: 0.0,
+        magenta: 0.0,
+        yellow: 0.0,
+        black: 1.0,
+    };
+    pub const WHITE: Cmyk = Cmyk {
+        cyan: 0.0,
+        magenta: 0.0,
+        yellow: 0.0,
+        black: 0.0,
+    };
+    fn operands(&self) -> String {
+        format!(
+            "{} {} {} {}",
+            number(self.cyan),
+            number(self.magenta),
+            number(self.yellow),
+            number(self.black)
+        )
+    }
+}
+
+/// `width` is the trimmed artwork, quiet zone included; the height follows
+/// from the symbol's aspect ratio. `bleed` is added outside the trim on every
+/// side and only carries the `light` background, which is left unpainted
+/// (paper) when `None`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrintOptions {
+    pub width: Length,
+    pub bleed: Length,
+    /// Light modules around the symbol; `None` keeps the symbol's own
+    /// `quiet_zone()`.
+    pub quiet_zone: Option<u32>,
+    pub dark: Cmyk,
+    pub light: Option<Cmyk>,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions {
+            width: Length::Millimetres(25.0),
+            bleed: Length::Points(0.0),
+            quiet_zone: None,
+            dark: Cmyk::BLACK,
+            light: None,
+        }
+    }
+}
+
+/// Page geometry in points, origin bottom left as in PostScript and PDF.
+struct Artwork {
+    width: f32,
+    height: f32,
+    /// Trim box, `(x, y, width, height)`.
+    trim: (f32, f32, f32, f32),
+    /// Outlines of the dark regions, holes included.
+    polygons: Vec<Vec<(f32, f32)>>,
+}
+
+impl Artwork {
+    fn new(symbol: &dyn Symbol, options: &PrintOptions) -> Artwork {
+        let quiet_zone = options.quiet_zone.unwrap_or(symbol.quiet_zone());
+        let rows = padded_rows(symbol, quiet_zone);
+        let module = options.width.points() / rows[0].len() as f32;
+        let bleed = options.bleed.points();
+        let trim_height = module * rows.len() as f32;
+        let polygons = contour::trace(&rows)
+            .iter()
+            .map(|polygon| {
+                polygon
+                    .iter()
+                    .map(|(x, y)| {
+                        (
+                            bleed + *x as f32 * module,
+                            bleed + trim_height - *y as f32 * module,
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+        Artwork {
+            width: options.width.points() + 2.0 * bleed,
+            height: trim_height + 2.0 * bleed,
+            trim: (bleed, bleed, options.width.points(), trim_height),
+            polygons,
+        }
+    }
+    /// The outlines with the given move, line and close operators.
+    fn path(&self, move_to: &str, line_to: &str, close: &str) -> String {
+        let mut path = String::new();
+        for polygon in &self.polygons {
+            for (i, (x, y)) in polygon.iter().enumerate() {
+                path += &format!(
+                    "{} {} {}\n",
+                    number(*x),
+                    number(*y),
+                    if i == 0 { move_to } else { line_to }
+                );
+            }
+            path += close;
+            path += "\n";
+        }
+        path
+    }
+}
+
+/// Encapsulated PostScript of the symbol, bounding box including the bleed.
+pub fn to_eps(symbol: &dyn Symbol, options: &PrintOptions) -> String {
+    let artwork = Artwork::new(symbol, options);
+    let mut eps = format!(
+        concat!(
+            "%!PS-Adobe-3.0 EPSF-3.0\n",
+            "%%BoundingBox: 0 0 {} {}\n",
+            "%%HiResBoundingBox: 0 0 {} {}\n",
+            "%%Creator: qrcode\n",
+            "%%LanguageLevel: 2\n",
+            "%%EndComments\n",
+            "save\n"
+        ),
+        artwork.width.ceil(),
+        artwork.height.ceil(),
+        number(artwork.width),
+        number(artwork.height)
+    );
+    if let Some(light) = options.light {
+        eps += &format!(
+            "{} setcmykcolor\n0 0 {} {} rectfill\n",
+            light.operands(),
+            number(artwork.width),
+            number(artwork.height)
+        );
+    }
+    eps += &format!("{} setcmykcolor\n", options.dark.operands());
+    eps += &artwork.path("moveto", "lineto", "closepath");
+    eps + "fill\nrestore\nshowpage\n%%EOF\n"
+}
+
+/// Single-page PDF of the symbol. The media box includes the bleed, the
+/// trim box is the artwork itself.
+pub fn to_pdf(symbol: &dyn Symbol, options: &PrintOptions) -> Vec<u8> {
+    let artwork = Artwork::new(symbol, options);
+    let mut content = String::new();
+    if let Some(light) = options.light {
+        content += &format!(
+            "{} k\n0 0 {} {} re f\n",
+            light.operands(),
+            number(artwork.width),
+            number(artwork.height)
+        );
+    }
+    content += &format!("{} k\n", options.dark.operands());
+    content += &artwork.path("m", "l", "h");
+    content += "f\n";
+    let trim = (
+        artwork.trim.0,
+        artwork.trim.1,
+        artwork.trim.0 + artwork.trim.2,
+        artwork.trim.1 + artwork.trim.3,
+    );
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {0} {1}] /BleedBox [0 0 {0} {1}] /TrimBox [{2} {3} {4} {5}] /Resources << >> /Contents 4 0 R >>",
+            number(artwork.width),
+            number(artwork.height),
+            number(trim.0),
+            number(trim.1),
+            number(trim.2),
+            number(trim.3)
+        ),
+        format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content),
+    ];
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf += &format!("{} 0 obj\n{}\nendobj\n", i + 1, object);
+    }
+    let xref = pdf.len();
+    pdf += &format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        pdf += &format!("{:010} 00000 n \n", offset);
+    }
+    pdf += &format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.into_bytes()
+}
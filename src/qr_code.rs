@@ -1,12 +1,88 @@
-#[path = "reed_solomon.rs"]
-mod reed_solomon;
+use crate::capacity;
 use crate::config::ByteNameOfMode;
 use crate::config::ErrorLevel;
 use crate::config::Mask;
+use crate::config::Model;
+use crate::config::ModuleKind;
+use crate::config::Selection;
+use crate::config::EXTENSION_PATTERN;
+use crate::error::Error;
+use crate::reed_solomon::ReedSolomonData;
+use crate::render;
+use crate::render::Symbol;
+use crate::render::QUIET_ZONE;
+use crate::report::EncodingReport;
+use crate::trace::EncodingTrace;
 use crate::utils::get_alignment;
 use crate::utils::mask;
-use reed_solomon::ReedSolomonData;
 use std::fmt;
+use std::io::Read;
+/// Modules of both copies of the format information, most significant bit
+/// first: around the top left finder, then split between the bottom left
+/// and top right finders.
+pub(crate) fn format_positions(size: u32) -> [[(u32, u32); 15]; 2] {
+    let mut copies = [[(0, 0); 15]; 2];
+    for i in 0..15 {
+        copies[0][i as usize] = match i {
+            0..=5 => (i, 8),
+            6 => (7, 8),
+            7 => (8, 8),
+            8 => (8, 7),
+            _ => (8, 14 - i),
+        };
+        copies[1][i as usize] = if i < 7 {
+            (8, size - 1 - i)
+        } else {
+            (size - 15 + i, 8)
+        };
+    }
+    copies
+}
+/// Modules of every codeword of a QR Model 1 symbol, most significant bit
+/// first: 2x4 blocks up the right edge, 4x2 blocks column by column from
+/// right to left, then 2x4 blocks between the left finders. Blocks start
+/// at their bottom right module. The first codeword only has 4 bits, the
+/// other 4 sit in the bottom right corner.
+pub(crate) fn model1_codeword_positions(size: u32) -> Vec<[(u32, u32); 8]> {
+    let columns = size / 4 + 3;
+    let block = |x: u32, y: u32, width: u32| {
+        let mut modules = [(0, 0); 8];
+        for (bit, module) in modules.iter_mut().enumerate() {
+            let bit = bit as u32;
+            *module = (x - bit % width, y - bit / width);
+        }
+        modules
+    };
+    let mut codewords = Vec::new();
+    for j in 0..columns {
+        if j <= 1 {
+            let rows = (size - 8) / 4;
+            for i in 0..rows {
+                if j == 0 && i % 2 == 0 && i > 0 && i < rows - 1 {
+                    continue;
+                }
+                codewords.push(block(size - 1 - j * 2, size - 1 - i * 4, 2));
+            }
+        } else if columns - j <= 4 {
+            // Columns 8 and 7, 5 and 4, 3 and 2, 1 and 0, around the timing
+            // pattern.
+            let x = (columns - j - 1) * 2 + 1 + if columns - j == 4 { 1 } else { 0 };
+            for i in 0..(size - 16) / 4 {
+                codewords.push(block(x, size - 9 - i * 4, 2));
+            }
+        } else {
+            let rows = size / 2;
+            for i in 0..rows {
+                if (j == 2 && i >= rows - 4) || (i == 0 && j % 2 == 1 && j + 1 != columns - 4) {
+                    continue;
+                }
+                let timing = if i >= rows - 3 { 1 } else { 0 };
+                codewords.push(block(size - 5 - (j - 2) * 4, size - 1 - i * 2 - timing, 4));
+            }
+        }
+    }
+    codewords
+}
 pub struct Matrix<T> {
     size_x: u32,
     size_y: u32,
@@ -21,31 +97,29 @@ where
         for i in 0..self.size_y {
             let mut row: String = "|".to_string();
             for j in 0..self.size_x {
-                row += &String::from(format!("{:?}|", self.matrix[i as usize][j as usize]))
+                row += &format!("{:?}|", self.matrix[i as usize][j as usize])
             }
-            println!("{:?}", row);
+            writeln!(f, "{:?}", row)?;
         }
-        write!(f, "")
+        Ok(())
     }
 }
 impl fmt::Debug for QRcode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for i in 0..self.qrcode.size_y {
-            let mut row: String = "".to_string();
-            for j in 0..self.qrcode.size_x {
-                row += if format!("{:?}", self.qrcode.matrix[i as usize][j as usize]) == "1" {
-                    "██"
-                } else {
-                    if format!("{:?}", self.qrcode.matrix[i as usize][j as usize]) == "0" {
-                        "  "
-                    } else {
-                        "░░"
-                    }
-                }
-            }
-            println!("{:?}", row);
-        }
-        write!(f, "")
+        render::fmt_modules(self, f)
+    }
+}
+impl fmt::Display for QRcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", render::to_half_blocks(self, false))
+    }
+}
+impl Symbol for QRcode {
+    fn modules(&self) -> &Matrix<u8> {
+        &self.qrcode
+    }
+    fn quiet_zone(&self) -> u32 {
+        self.quiet_zone
     }
 }
 
@@ -55,16 +129,16 @@ where
 {
     pub fn create(size_x: u32, size_y: u32, filler: &T) -> Matrix<T> {
         let mut vec_table: Vec<Vec<T>> = Vec::with_capacity((size_y) as usize);
-        for i in 0..(size_y) {
+        for _ in 0..(size_y) {
             let mut vec_row: Vec<T> = Vec::with_capacity((size_x) as usize);
-            for i in 0..(size_x) {
+            for _ in 0..(size_x) {
                 vec_row.push(*filler);
             }
             vec_table.push(vec_row);
         }
         Matrix::<T> {
-            size_x: size_x,
-            size_y: size_y,
+            size_x,
+            size_y,
             matrix: vec_table,
         }
     }
@@ -72,7 +146,10 @@ where
         self.matrix[point.1 as usize][point.0 as usize] = value
     }
     pub fn get(&self, point: (u32, u32)) -> T {
-        return self.matrix[point.1 as usize][point.0 as usize];
+        self.matrix[point.1 as usize][point.0 as usize]
+    }
+    pub fn size(&self) -> (u32, u32) {
+        (self.size_x, self.size_y)
     }
     pub fn transpose(&mut self) {
         let mut new_matrix: Matrix<T> =
@@ -84,12 +161,12 @@ where
         }
         *self = new_matrix;
     }
-    pub fn put_vec(&mut self, point: (u32, u32), vec: &Vec<T>, horizontal: bool) {
-        for i in 0..vec.len() {
+    pub fn put_vec(&mut self, point: (u32, u32), vec: &[T], horizontal: bool) {
+        for (i, value) in vec.iter().enumerate() {
             if horizontal {
-                self.matrix[point.1 as usize][point.0 as usize + i] = vec[i];
+                self.matrix[point.1 as usize][point.0 as usize + i] = *value;
             } else {
-                self.matrix[point.1 as usize + i][point.0 as usize] = vec[i];
+                self.matrix[point.1 as usize + i][point.0 as usize] = *value;
             }
         }
     }
@@ -106,23 +183,23 @@ where
     pub fn rotate(&mut self) {
         let len = self.size_x - 1;
         if self.size_y == (len + 1) {
-            let i_range = (len - 1 / 2) as f32;
+            let i_range = len as f32;
             for i in 0..i_range.floor() as u32 {
-                let part_1: f32 = (len - 1) as f32 / 2 as f32;
-                let part_2: f32 = (len + 1) as f32 / 2 as f32;
+                let part_1: f32 = (len - 1) as f32 / 2_f32;
+                let part_2: f32 = (len + 1) as f32 / 2_f32;
 
                 for j in i..(part_1.floor() as u32 + part_2.ceil() as u32 - i) {
-                    let base: T = self.matrix[(0 + i) as usize][(0 + j) as usize];
-                    self.matrix[(0 + i) as usize][(0 + j) as usize] =
-                        self.matrix[(len - j) as usize][(0 + i) as usize];
+                    let base: T = self.matrix[i as usize][j as usize];
+                    self.matrix[i as usize][j as usize] =
+                        self.matrix[(len - j) as usize][i as usize];
 
-                    self.matrix[(len - j) as usize][(0 + i) as usize] =
+                    self.matrix[(len - j) as usize][i as usize] =
                         self.matrix[(len - i) as usize][(len - j) as usize];
 
                     self.matrix[(len - i) as usize][(len - j) as usize] =
-                        self.matrix[(0 + j) as usize][(len - i) as usize];
+                        self.matrix[j as usize][(len - i) as usize];
 
-                    self.matrix[(0 + j) as usize][(len - i) as usize] = base
+                    self.matrix[j as usize][(len - i) as usize] = base
                 }
             }
         } else {
@@ -138,34 +215,219 @@ where
     }
 }
 
+/// Requested version and error level, how the final ones are selected
+/// from them, mask and model of a symbol, and the quiet zone its renderers
+/// draw.
+#[derive(Debug, Copy, Clone)]
+pub struct QRcodeOptions {
+    pub version: u8,
+    pub error_level: ErrorLevel,
+    pub selection: Selection,
+    /// Largest version `selection` may grow to.
+    pub max_version: u8,
+    pub mask: Mask,
+    /// Model 2, versions 1 to 40, or the older Model 1, versions 1 to 14
+    /// with `max_version` capped at 14.
+    pub model: Model,
+    pub quiet_zone: u32,
+}
+
+impl Default for QRcodeOptions {
+    fn default() -> QRcodeOptions {
+        QRcodeOptions {
+            version: 1,
+            error_level: ErrorLevel::L,
+            selection: Selection::SmallestVersion,
+            max_version: 40,
+            mask: Mask::_100,
+            model: Model::Model2,
+            quiet_zone: QUIET_ZONE,
+        }
+    }
+}
+
 pub struct QRcode {
     pub rs: ReedSolomonData,
     pub size: u8,
+    /// Light modules the renderers add around the symbol; not part of the
+    /// matrix.
+    pub quiet_zone: u32,
     qrcode: Matrix<u8>,
+    kinds: Matrix<ModuleKind>,
+    placement: Vec<(u32, u32)>,
 }
 
 impl QRcode {
     pub fn new(data: &str, version: u8, error_correct: ErrorLevel, mask: Mask) -> QRcode {
-        let reed_solomon: ReedSolomonData =
-            ReedSolomonData::new(data, error_correct, version, mask, ByteNameOfMode::Byte);
+        let reed_solomon: ReedSolomonData = ReedSolomonData::new(
+            data.as_bytes(),
+            error_correct,
+            version,
+            mask,
+            ByteNameOfMode::Byte,
+        );
+        QRcode::from_reed_solomon(reed_solomon)
+    }
+    pub(crate) fn from_reed_solomon(reed_solomon: ReedSolomonData) -> QRcode {
         let size = 21 + (reed_solomon.version - 1) * 4;
-        let qrcode = QRcode {
+
+        QRcode {
             rs: reed_solomon,
-            size: size,
+            size,
+            quiet_zone: QUIET_ZONE,
             qrcode: Matrix::<u8>::create(size as u32, size as u32, &2),
+            kinds: Matrix::create(size as u32, size as u32, &ModuleKind::Empty),
+            placement: Vec::new(),
+        }
+    }
+    /// Intermediate results of every encoding stage.
+    pub fn trace(&self) -> EncodingTrace {
+        EncodingTrace::new(self)
+    }
+    /// Bit budget, block structure and penalty; render the symbol first
+    /// for a meaningful penalty.
+    pub fn report(&self) -> EncodingReport {
+        EncodingReport::new(self)
+    }
+    /// Modules of the bit stream in the order `push_data_strip` filled
+    /// them, index `i` holding bit `i` of `rs.bits`.
+    pub fn placement(&self) -> &[(u32, u32)] {
+        &self.placement
+    }
+    pub(crate) fn put_module(&mut self, point: (u32, u32), value: u8) {
+        self.qrcode.put(point, value);
+    }
+    /// Role of every module, parallel to the rendered matrix.
+    pub fn module_kinds(&self) -> &Matrix<ModuleKind> {
+        &self.kinds
+    }
+    fn mark(&mut self, point: (u32, u32), size: (u32, u32), kind: ModuleKind) {
+        for y in point.1..(point.1 + size.1) {
+            for x in point.0..(point.0 + size.0) {
+                self.kinds.put((x, y), kind);
+            }
+        }
+    }
+    /// Kind of the bit at `position` in `rs.bits`: data and error
+    /// correction codewords, then the remainder bits.
+    fn bit_kind(&self, position: usize) -> ModuleKind {
+        let data_codewords = self.rs.error_correction_data().0;
+        let codewords = (self.rs.bits.len() - self.rs.remainder_bits()) / 8;
+        let codeword = (position / 8) as u16;
+        let bit = 7 - (position % 8) as u8;
+        if position >= codewords * 8 {
+            ModuleKind::Remainder
+        } else if codeword < data_codewords {
+            ModuleKind::Data { codeword, bit }
+        } else {
+            ModuleKind::ErrorCorrection { codeword, bit }
+        }
+    }
+    pub fn with_options(data: &str, options: QRcodeOptions) -> Result<QRcode, Error> {
+        QRcode::from_bytes(data.as_bytes(), options)
+    }
+    /// Raw bytes in a single byte mode segment, no text encoding assumed.
+    pub fn from_bytes(data: &[u8], options: QRcodeOptions) -> Result<QRcode, Error> {
+        let max_version = match options.model {
+            Model::Model1 => {
+                if options.version == 0 || options.version > 14 {
+                    return Err(Error::InvalidOption("QR Model 1 version is 1..14"));
+                }
+                options.max_version.min(14)
+            }
+            Model::Model2 => options.max_version,
         };
-        return qrcode;
+        if options.version == 0 || max_version > 40 {
+            return Err(Error::InvalidOption("QR Model 2 version is 1..40"));
+        }
+        if options.version > max_version {
+            return Err(Error::InvalidOption("version above max_version"));
+        }
+        let reed_solomon = ReedSolomonData::with_selection(
+            data,
+            options.error_level,
+            options.version..=max_version,
+            options.selection,
+            options.mask,
+            ByteNameOfMode::Byte,
+            options.model,
+        )?;
+        let mut qrcode = QRcode::from_reed_solomon(reed_solomon);
+        qrcode.quiet_zone = options.quiet_zone;
+        Ok(qrcode)
     }
-    fn margin(&mut self) {
-        let size = (self.size + 6) as u32;
-        let mut margin: Matrix<u8> = Matrix::create(size, size, &1);
-        margin.put_matrix((3, 3), &self.qrcode);
-        self.qrcode = margin;
+    /// `from_bytes` with everything `reader` yields; stops reading once the
+    /// data is too long for any symbol.
+    pub fn from_reader(reader: impl Read, options: QRcodeOptions) -> Result<QRcode, Error> {
+        let limit = capacity::characters(ByteNameOfMode::Byte, 40, ErrorLevel::L) as u64 + 1;
+        let mut data: Vec<u8> = Vec::new();
+        reader
+            .take(limit)
+            .read_to_end(&mut data)
+            .map_err(|error| Error::Io(error.kind()))?;
+        QRcode::from_bytes(&data, options)
     }
     pub fn render(&mut self) {
         self.set_patterns();
         self.insert_data();
-        self.margin();
+    }
+    /// Mask penalty scores N1 (runs), N2 (2x2 blocks), N3 (finder-like
+    /// patterns) and N4 (dark proportion) of the symbol, quiet zone excluded.
+    pub fn penalty(&self) -> [u32; 4] {
+        let size = self.size as u32;
+        let dark = |x: u32, y: u32| self.qrcode.get((x, y)) == 0;
+        let mut penalty: [u32; 4] = [0; 4];
+        for line in 0..size {
+            for horizontal in [true, false] {
+                let cell = |i: u32| {
+                    if horizontal {
+                        dark(i, line)
+                    } else {
+                        dark(line, i)
+                    }
+                };
+                let mut run = 1;
+                for i in 1..=size {
+                    if i < size && cell(i) == cell(i - 1) {
+                        run += 1;
+                        continue;
+                    }
+                    if run >= 5 {
+                        penalty[0] += 3 + run - 5;
+                    }
+                    run = 1;
+                }
+                let light = |from: i64, to: i64| {
+                    (from.max(0)..to.min(size as i64)).all(|i| !cell(i as u32))
+                };
+                for i in 0..size.saturating_sub(6) {
+                    let finder_like = [true, false, true, true, true, false, true]
+                        .iter()
+                        .enumerate()
+                        .all(|(j, value)| cell(i + j as u32) == *value);
+                    let i = i as i64;
+                    if finder_like && (light(i - 4, i) || light(i + 7, i + 11)) {
+                        penalty[2] += 40;
+                    }
+                }
+            }
+        }
+        for y in 0..(size - 1) {
+            for x in 0..(size - 1) {
+                let color = dark(x, y);
+                if dark(x + 1, y) == color && dark(x, y + 1) == color && dark(x + 1, y + 1) == color
+                {
+                    penalty[1] += 3;
+                }
+            }
+        }
+        let dark_modules = (0..size)
+            .flat_map(|y| (0..size).map(move |x| (x, y)))
+            .filter(|(x, y)| dark(*x, *y))
+            .count() as u32;
+        let total = size * size;
+        penalty[3] = (dark_modules * 2).abs_diff(total) * 10 / total * 10;
+        penalty
     }
     pub fn push_data_strip(&mut self, vec: &mut Vec<u8>, up: bool, point: (u32, u32), swap: u16) {
         let mut y = if up {
@@ -173,12 +435,11 @@ impl QRcode {
         } else {
             point.1
         };
-        let mut i = 0;
         let mut add = 0;
         let mut index: u16 = swap;
         let len = vec.len();
         while index <= (len + add) as u16 {
-            if (up && y < 0) || (!up && y > self.qrcode.size_y - 1) {
+            if !up && y > self.qrcode.size_y - 1 {
                 break;
             }
             let p_0 = (self.qrcode.matrix[y as usize].len() as i16 - 2) + (index % 2) as i16
@@ -186,7 +447,7 @@ impl QRcode {
             if p_0 < 0 || self.qrcode.matrix[y as usize][p_0 as usize] != 2 {
                 // to repair
                 add += 1;
-                if index % 2 == 0 {
+                if index.is_multiple_of(2) {
                     if up && y > 0 {
                         y -= 1
                     } else {
@@ -196,9 +457,10 @@ impl QRcode {
                 index += 1;
                 continue;
             }
-            if vec.len() == 0 {
+            if vec.is_empty() {
                 break;
             };
+            let position = self.rs.bits.len() - vec.len();
             let mut value = (*vec).pop().unwrap();
             value = if mask(self.rs.mask, p_0 as u16, y as u16) {
                 value
@@ -210,8 +472,10 @@ impl QRcode {
                 }
             };
             self.qrcode.matrix[y as usize][p_0 as usize] = value;
+            self.kinds.put((p_0 as u32, y), self.bit_kind(position));
+            self.placement.push((p_0 as u32, y));
 
-            if index % 2 == 0 {
+            if index.is_multiple_of(2) {
                 if up && y > 0 {
                     y -= 1
                 } else {
@@ -219,10 +483,12 @@ impl QRcode {
                 }
             }
             index += 1;
-            i += 1
         }
     }
     pub fn insert_data(&mut self) {
+        if self.rs.model == Model::Model1 {
+            return self.insert_model1_data();
+        }
         let mut data_set: Vec<u8> = Vec::new();
 
         for i in self.rs.bits.chars() {
@@ -231,18 +497,29 @@ impl QRcode {
         data_set.reverse();
         let mut col = 0;
         for i in 0..(self.size / 2) {
-            self.push_data_strip(
-                &mut data_set,
-                if i % 2 == 0 { true } else { false },
-                (col, 0),
-                1,
-            );
+            self.push_data_strip(&mut data_set, i % 2 == 0, (col, 0), 1);
             col += 2;
             if col == self.size as u32 - 7 || col == self.size as u32 - 6 {
                 col += 1
             }
         }
     }
+    /// Bits in the blocks of `model1_codeword_positions`, masked like
+    /// Model 2 data.
+    fn insert_model1_data(&mut self) {
+        let bits: Vec<u8> = self.rs.bits.bytes().map(|bit| bit - b'0').collect();
+        let modules = model1_codeword_positions(self.size as u32).concat();
+        for (position, (bit, (x, y))) in bits.iter().zip(modules).enumerate() {
+            let value = if mask(self.rs.mask, x as u16, y as u16) {
+                *bit
+            } else {
+                1 - *bit
+            };
+            self.qrcode.put((x, y), value);
+            self.kinds.put((x, y), self.bit_kind(position));
+            self.placement.push((x, y));
+        }
+    }
     pub fn create_align(&mut self) {
         let align: &Vec<u32> = &get_alignment(self.rs.version);
         let mut align_matrix: Matrix<u8> = Matrix::create(5, 5, &0);
@@ -255,24 +532,52 @@ impl QRcode {
                 if self.qrcode.get((*i, *j)) != 2 {
                     continue;
                 }
-                self.qrcode.put_matrix((*i - 2, *j - 2), &align_matrix)
+                self.qrcode.put_matrix((*i - 2, *j - 2), &align_matrix);
+                self.mark((*i - 2, *j - 2), (5, 5), ModuleKind::Alignment);
             }
         }
     }
     pub fn set_patterns(&mut self) {
         self.create_finder();
         self.black_module();
-        if self.rs.version > 2 {
+        if self.rs.model == Model::Model1 {
+            self.create_extension();
+        } else if self.rs.version > 2 {
             self.create_align()
         };
         self.create_timing();
         self.create_format_string();
-        if self.rs.version >= 7 {
+        if self.rs.model == Model::Model2 && self.rs.version >= 7 {
             self.create_version_string()
         }
     }
+    /// Model 1 extension patterns: every other block of the right edge
+    /// between the first and the last, and of the bottom edge from the
+    /// second block left of the corner on.
+    pub fn create_extension(&mut self) {
+        let size = self.size as u32;
+        let mut pattern: Matrix<u8> = Matrix::create(2, 4, &0);
+        for (y, row) in EXTENSION_PATTERN.iter().enumerate() {
+            pattern.put_vec((0, y as u32), row, true);
+        }
+        let blocks = (size - 8) / 4;
+        for i in (2..blocks - 1).step_by(2) {
+            let point = (size - 2, size - 4 - i * 4);
+            self.qrcode.put_matrix(point, &pattern);
+            self.mark(point, (2, 4), ModuleKind::Extension);
+        }
+        pattern.transpose();
+        let columns = size / 4 + 3;
+        for j in (3..columns - 5).step_by(2) {
+            let point = (size - 8 - (j - 2) * 4, size - 2);
+            self.qrcode.put_matrix(point, &pattern);
+            self.mark(point, (4, 2), ModuleKind::Extension);
+        }
+    }
     pub fn black_module(&mut self) {
-        self.qrcode.put((8, self.qrcode.size_y - 8), 0)
+        self.qrcode.put((8, self.qrcode.size_y - 8), 0);
+        self.kinds
+            .put((8, self.qrcode.size_y - 8), ModuleKind::DarkModule);
     }
     pub fn create_version_string(&mut self) {
         let version: String = self.rs.create_version_string();
@@ -283,45 +588,40 @@ impl QRcode {
         let mut version_matrix: Matrix<u8> = Matrix::create(3, 6, &0);
         for i in 0..6 {
             let mut pop_vec: Vec<u8> = Vec::new();
-            for j in 0..3 {
+            for _ in 0..3 {
                 let value = version_vec.pop().unwrap();
-                pop_vec.push(value as u8);
+                pop_vec.push(value);
             }
             version_matrix.put_vec((0, i), &(pop_vec.to_vec()), true)
         }
         self.qrcode
             .put_matrix((self.size as u32 - 11, 0), &version_matrix);
+        self.mark((self.size as u32 - 11, 0), (3, 6), ModuleKind::Version);
         version_matrix.transpose();
         self.qrcode
             .put_matrix((0, self.size as u32 - 11), &version_matrix);
+        self.mark((0, self.size as u32 - 11), (6, 3), ModuleKind::Version);
     }
     pub fn create_format_string(&mut self) {
         let format: String = self.rs.create_format_string();
-        let mut format_vec: Vec<u8> = Vec::new();
-        for i in format.chars() {
-            format_vec.push(if i.to_digit(10).unwrap() == 1 { 0 } else { 1 });
-        }
-        let scrap: Vec<u8> = format_vec[(format_vec.len() - 2)..].to_vec();
-        for i in 0..2 {
-            let point = i % 2 == 0;
-            let mut cord: [(u32, u32); 3] = [(0, 8), (7, 8), ((self.size - 8) as u32, 8)];
-            if !point {
-                format_vec.reverse();
-                cord = [(8, 0), (8, 7), (8, (self.size - 7) as u32)];
-                format_vec.remove(7);
+        for copy in format_positions(self.size as u32) {
+            for (bit, point) in format.chars().zip(copy) {
+                self.qrcode.put(point, if bit == '1' { 0 } else { 1 });
+                self.kinds.put(point, ModuleKind::Format);
             }
-            self.qrcode
-                .put_vec(cord[0], &format_vec[..6].to_vec(), point);
-            self.qrcode
-                .put_vec(cord[1], &format_vec[6..8].to_vec(), point);
-            self.qrcode
-                .put_vec(cord[2], &format_vec[7..].to_vec(), point);
         }
     }
     pub fn create_timing(&mut self) {
         let timing: Vec<u8> = (0..(self.size - 14)).map(|x| x % 2).collect();
         self.qrcode.put_vec((6, 6), &timing, true);
         self.qrcode.put_vec((6, 6), &timing, false);
+        for i in 8..(self.size as u32 - 8) {
+            for point in [(i, 6), (6, i)] {
+                if self.kinds.get(point) == ModuleKind::Empty {
+                    self.kinds.put(point, ModuleKind::Timing);
+                }
+            }
+        }
     }
     pub fn create_finder(&mut self) {
         let mut finder: Matrix<u8> = Matrix::create(8, 8, &0);
@@ -329,8 +629,8 @@ impl QRcode {
         let black_finder = Matrix::<u8>::create(3, 3, &0);
         finder.put_matrix((1, 1), &white_finder);
         finder.put_matrix((2, 2), &black_finder);
-        finder.put_vec((7, 0), &[1; 8].to_vec(), false);
-        finder.put_vec((0, 7), &[1; 8].to_vec(), true);
+        finder.put_vec((7, 0), &[1; 8], false);
+        finder.put_vec((0, 7), &[1; 8], true);
 
         self.qrcode.put_matrix((0, 0), &finder);
         finder.rotate();
@@ -338,5 +638,15 @@ impl QRcode {
         finder.rotate();
         finder.rotate();
         self.qrcode.put_matrix((0, self.size as u32 - 8), &finder);
+
+        let far = self.size as u32 - 8;
+        for (corner, finder_corner) in [
+            ((0, 0), (0, 0)),
+            ((far, 0), (far + 1, 0)),
+            ((0, far), (0, far + 1)),
+        ] {
+            self.mark(corner, (8, 8), ModuleKind::Separator);
+            self.mark(finder_corner, (7, 7), ModuleKind::Finder);
+        }
     }
 }
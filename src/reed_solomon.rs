@@ -1,147 +1,230 @@
+use crate::bch;
 use crate::config::ByteNameOfMode;
 use crate::config::ErrorLevel;
 use crate::config::Mask;
+use crate::config::Model;
+use crate::config::Selection;
 use crate::config::BLANK_FILLER;
-use crate::config::FORMAT_STRING_XOR_VALUE;
+use crate::config::ERROR_LEVELS;
 use crate::config::LEVEL_INDICATOR;
 use crate::config::MODE_INDICATOR;
 use crate::config::REMINDER;
+use crate::error::Error;
 use crate::polynomial::div_polynomial;
 use crate::utils;
+use std::ops::RangeInclusive;
 
 pub struct ReedSolomonData {
-    data: String,
+    data: Vec<u8>,
     pub error_level: ErrorLevel,
     pub version: u8,
     pub mask: Mask,
     pub mode: ByteNameOfMode,
+    pub model: Model,
     pub bits: String,
 }
 
 impl ReedSolomonData {
     pub fn new(
-        data: &str,
+        data: &[u8],
         min_error_level: ErrorLevel,
         min_version: u8,
         mask: Mask,
         mode: ByteNameOfMode,
     ) -> ReedSolomonData {
+        ReedSolomonData::with_selection(
+            data,
+            min_error_level,
+            min_version..=40,
+            Selection::SmallestVersion,
+            mask,
+            mode,
+            Model::Model2,
+        )
+        .expect("data too long for a version 40 symbol")
+    }
+    /// Codewords of `data` at the version and error level `selection`
+    /// picks from `versions`.
+    pub fn with_selection(
+        data: &[u8],
+        error_level: ErrorLevel,
+        versions: RangeInclusive<u8>,
+        selection: Selection,
+        mask: Mask,
+        mode: ByteNameOfMode,
+        model: Model,
+    ) -> Result<ReedSolomonData, Error> {
         let mut reed_solomon = ReedSolomonData {
-            data: String::from(data),
+            data: data.to_vec(),
             error_level: ErrorLevel::L,
             version: 1,
-            mask: mask,
-            mode: mode,
+            mask,
+            mode,
+            model,
             bits: "".to_string(),
         };
-        reed_solomon.get_version_error_level(min_error_level, min_version);
+        reed_solomon.select(error_level, *versions.start(), *versions.end(), selection)?;
         reed_solomon.generate_data_bits();
-        reed_solomon
+        Ok(reed_solomon)
+    }
+    /// `utils::get_error_correction_level_data` of the model of the symbol.
+    pub fn error_correction_data(&self) -> (u16, u16, [u16; 2], [f32; 2], u16) {
+        match self.model {
+            Model::Model1 => {
+                utils::get_model1_error_correction_level_data(self.version, &self.error_level)
+            }
+            Model::Model2 => {
+                utils::get_error_correction_level_data(self.version, &self.error_level)
+            }
+        }
+    }
+    /// Zero bits after the last codeword; in Model 1 whole codewords the
+    /// blocks leave over.
+    pub fn remainder_bits(&self) -> usize {
+        match self.model {
+            Model::Model1 => {
+                let (data_codewords, blocks, _, _, error_correction) = self.error_correction_data();
+                (utils::get_model1_total_codewords(self.version)
+                    - data_codewords
+                    - blocks * error_correction) as usize
+                    * 8
+            }
+            Model::Model2 => REMINDER[(self.version - 1) as usize],
+        }
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    /// The same codewords under another mask.
+    pub fn with_mask(&self, mask: Mask) -> ReedSolomonData {
+        ReedSolomonData {
+            data: self.data.clone(),
+            mask,
+            bits: self.bits.clone(),
+            ..*self
+        }
     }
+    /// Format information as 15 bits, most significant first.
     pub fn create_format_string(&self) -> String {
-        let mask = match self.mask {
-            Mask::_000 => "000",
-            Mask::_001 => "001",
-            Mask::_010 => "010",
-            Mask::_011 => "011",
-            Mask::_100 => "100",
-            Mask::_101 => "101",
-            Mask::_110 => "110",
-            Mask::_111 => "111",
+        let data = (LEVEL_INDICATOR[self.error_level as usize] as u32) << 3 | self.mask as u32;
+        let codeword = match self.model {
+            Model::Model1 => bch::model1_format_codeword(data),
+            Model::Model2 => bch::format_codeword(data),
         };
-        let level_mask = format!("{:02b}", LEVEL_INDICATOR[self.error_level as usize]) + &mask;
-        let div_format_str = self.main_string_format(&level_mask, 15, 0, 10);
-        //println!("•• {:?}", div_format_str);
-        let combine_format_str = format!(
-            "{:b}",
-            u32::from_str_radix(&(level_mask + &div_format_str), 2).unwrap()
-                ^ u32::from_str_radix(&FORMAT_STRING_XOR_VALUE, 2).unwrap()
-        );
-        "0".repeat(15 - combine_format_str.len()) + &combine_format_str
+        format!("{:015b}", codeword)
     }
+    /// Version information as 18 bits, most significant first.
     pub fn create_version_string(&self) -> String {
-        let version = format!("{:06b}", self.version);
-        let div_format_str = self.main_string_format(&version, 18, 1, 12);
-        //println!("• {:?}", div_format_str);
-        let combine_format_str = format!(
-            "{:b}",
-            u32::from_str_radix(&(version + &div_format_str), 2).unwrap()
-        );
-        "0".repeat(18 - combine_format_str.len()) + &combine_format_str
+        format!("{:018b}", bch::version_codeword(self.version))
     }
     pub fn generate_data_bits(&mut self) {
-        self.bits = self.create_reed_solomon_matrix().join("")
-            + &"0".repeat(REMINDER[(self.version - 1) as usize]);
+        self.bits = self.create_reed_solomon_matrix().join("") + &"0".repeat(self.remainder_bits());
     }
-    fn generate_content(&self) -> Vec<i16> {
-        let error_correction_data =
-            utils::get_error_correction_level_data(self.version, &self.error_level);
+    /// Mode indicator, length, data, terminator and pad codewords; Model 1
+    /// starts with 4 zero bits.
+    pub fn generate_content(&self) -> Vec<i16> {
+        let data_bits = self.error_correction_data().0 as usize * 8;
         let msg_len = self.data.len();
         let data_info_bin_len = utils::get_len_bit(&self.mode, self.version);
-        let codewords_diff = ((error_correction_data.0 as u16 - (data_info_bin_len as u16 >> 3) - 1)
-            as usize)
-            - msg_len;
-        let prepared_msg_len = String::from(format!("{msg_len:032b}"));
+        let prepared_msg_len = format!("{msg_len:032b}");
         let msg_data_bin_len = String::from(&prepared_msg_len[32 - data_info_bin_len as usize..]);
         let codewords_data = utils::get_bin_msg_data(&self.data);
         let mut bin_msg = vec![MODE_INDICATOR(&self.mode), msg_data_bin_len];
+        if self.model == Model::Model1 {
+            bin_msg.insert(0, String::from("0000"));
+        }
         for codeword in codewords_data {
             bin_msg.push(codeword);
         }
         bin_msg.push(String::from("0000"));
-        for i in 0..codewords_diff {
-            bin_msg.push(format!("{:08b}", BLANK_FILLER[(i % 2)]));
+        let mut joined_bin_msg = bin_msg.join("");
+        joined_bin_msg.truncate(data_bits);
+        while !joined_bin_msg.len().is_multiple_of(8) {
+            joined_bin_msg.push('0');
+        }
+        for i in 0..(data_bits - joined_bin_msg.len()) / 8 {
+            joined_bin_msg += &format!("{:08b}", BLANK_FILLER[i % 2]);
         }
-        let joined_bin_msg = bin_msg.join("");
         let mut next: usize = 0;
         let mut bin_message_codewords: Vec<i16> = Vec::new();
-        for i in 0..(joined_bin_msg.len() / 8) {
+        for _ in 0..(joined_bin_msg.len() / 8) {
             let codeword = &joined_bin_msg[next..(next + 8)];
-            next = next + 8;
+            next += 8;
             let int_value = u8::from_str_radix(codeword, 2).expect("not bin value");
             bin_message_codewords.push(int_value as i16);
         }
         bin_message_codewords
     }
-    fn create_reed_solomon_matrix(&self) -> Vec<String> {
+    /// Data codewords split into blocks of both groups, each with its
+    /// error correction codewords.
+    pub fn blocks(&self) -> Vec<(Vec<i16>, Vec<i16>)> {
         let mut codewords = self.generate_content();
-        let error_correction_data =
-            utils::get_error_correction_level_data(self.version, &self.error_level);
+        let error_correction_data = self.error_correction_data();
         let mut groups: Vec<(Vec<i16>, Vec<i16>)> = Vec::new();
-        let mut sub: f32 = 0.0;
-        let mut error_correction_data_number: u16 = 0;
         for group_number in 0..2 {
-            for i in 0..error_correction_data.2[group_number] {
-                sub = error_correction_data.3[group_number];
-                let mut group = codewords.splice(0..sub as usize, vec![]).collect();
-                let mut polynomial = div_polynomial(&mut group, error_correction_data.4 as i16);
-                let values: Vec<i16> = polynomial
-                    .data
-                    .iter_mut()
-                    .map(|value| {
-                        error_correction_data_number += 1;
-                        value.2
-                    })
-                    .collect();
+            for _ in 0..error_correction_data.2[group_number] {
+                let sub = error_correction_data.3[group_number];
+                let mut group: Vec<i16> = codewords.splice(0..sub as usize, vec![]).collect();
+                let polynomial = div_polynomial(&mut group, error_correction_data.4 as i16);
+                let values: Vec<i16> = polynomial.data.iter().map(|value| value.2).collect();
                 groups.push((group, values));
             }
         }
+        groups
+    }
+    /// Block an interleaved codeword was taken from: data codewords are read
+    /// column by column across all blocks, the longer second group finishing
+    /// alone, then error correction codewords the same way. Model 1 puts
+    /// the blocks one after the other instead.
+    pub fn codeword_block(&self, codeword: usize) -> usize {
+        let (data_codewords, blocks, groups, codewords_in_group, error_correction) =
+            self.error_correction_data();
+        let (data_codewords, blocks) = (data_codewords as usize, blocks as usize);
+        let shared = codewords_in_group[0] as usize * blocks;
+        if self.model == Model::Model1 {
+            if codeword >= data_codewords {
+                (codeword - data_codewords) / error_correction as usize
+            } else {
+                codeword / codewords_in_group[0] as usize
+            }
+        } else if codeword >= data_codewords {
+            (codeword - data_codewords) % blocks
+        } else if codeword < shared {
+            codeword % blocks
+        } else {
+            groups[0] as usize + codeword - shared
+        }
+    }
+    fn create_reed_solomon_matrix(&self) -> Vec<String> {
+        let error_correction_data = self.error_correction_data();
+        let groups = self.blocks();
+        // Model 1 places the blocks one after the other.
+        if self.model == Model::Model1 {
+            let (data, error_correction): (Vec<Vec<i16>>, Vec<Vec<i16>>) =
+                groups.into_iter().unzip();
+            return data
+                .concat()
+                .iter()
+                .chain(error_correction.concat().iter())
+                .map(|item| format!("{:08b}", item))
+                .collect();
+        }
+        let error_correction_data_number: usize = groups.iter().map(|block| block.1.len()).sum();
 
         let msg_codewords_number =
             (2 * error_correction_data.3[0] as u32 * error_correction_data.2[0] as u32
                 + error_correction_data.3[1] as u32 * error_correction_data.2[1] as u32)
                 as usize;
         let mut msg_codewords: Vec<i16> = vec![257; msg_codewords_number];
-        let mut error_correction_codewords: Vec<i16> =
-            vec![257; error_correction_data_number as usize];
+        let mut error_correction_codewords: Vec<i16> = vec![257; error_correction_data_number];
 
         for (i, block) in groups.iter().enumerate() {
             let provide_index =
                 |index: usize| i + (index + (index * (error_correction_data.1 - 1) as usize));
-            for (j, group) in (&block.0).iter().enumerate() {
+            for (j, group) in block.0.iter().enumerate() {
                 msg_codewords[provide_index(j)] = *group;
             }
-            for (j, polynomial) in (&block.1).iter().enumerate() {
+            for (j, polynomial) in block.1.iter().enumerate() {
                 error_correction_codewords[provide_index(j)] = *polynomial;
             }
         }
@@ -157,52 +240,48 @@ impl ReedSolomonData {
             })
             .collect()
     }
-    fn get_version_error_level(&mut self, min_error_level: ErrorLevel, min_version: u8) {
-        let len: u32 = self.data.len() as u32;
-        let error_levels = vec![ErrorLevel::L, ErrorLevel::Q, ErrorLevel::M, ErrorLevel::H];
-        let index_error_level = min_error_level as u8;
-        let available_error_levels = &error_levels[index_error_level as usize..];
-        for error_level in available_error_levels {
-            for version in min_version..=40 {
-                if utils::full_capacity(version, &error_level, &self.mode) >= len {
-                    self.version = version;
-                    self.error_level = *error_level;
-                    return;
-                }
-            }
-        }
-    }
-    fn xor_string_operator(
-        &self,
-        mut result: String,
-        limit: usize,
-        generator_polynomial: String,
-    ) -> String {
-        while result.len() > limit {
-            let tmp_generator_polynomial = (*generator_polynomial).to_string()
-                + &("0").repeat(result.len() - generator_polynomial.len());
-            result = format!(
-                "{:b}",
-                u32::from_str_radix(&result, 2).unwrap()
-                    ^ u32::from_str_radix(&tmp_generator_polynomial, 2).unwrap()
-            );
-        }
-        return result;
+    fn fits(&self, version: u8, error_level: &ErrorLevel) -> bool {
+        let capacity = match self.model {
+            Model::Model1 => utils::model1_full_capacity(version, error_level, &self.mode),
+            Model::Model2 => utils::full_capacity(version, error_level, &self.mode),
+        };
+        capacity >= self.data.len() as u32
     }
-    fn main_string_format(
-        &self,
-        data: &String,
-        data_bin_len: u16,
-        _type: usize,
-        bin_limit: u8,
-    ) -> String {
-        //println!("*-* {:?}", data_bin_len);
-        let prefix_from_string = utils::add_padding_without_prefix(&data, data_bin_len);
-        //println!("*** {:?}", prefix_from_string);
-        let generator_polynomial =
-            utils::get_array_bin_polynomial(_type, bin_limit, String::from(""));
-        let result =
-            self.xor_string_operator(prefix_from_string, bin_limit.into(), generator_polynomial);
-        "0".repeat((bin_limit - result.len() as u8) as usize) + &result
+    /// Picks version and error level following `selection`, versions from
+    /// `version` up to `max_version` and levels from `error_level` up.
+    fn select(
+        &mut self,
+        error_level: ErrorLevel,
+        version: u8,
+        max_version: u8,
+        selection: Selection,
+    ) -> Result<(), Error> {
+        let strength = ERROR_LEVELS
+            .iter()
+            .position(|level| *level as u8 == error_level as u8)
+            .unwrap();
+        let stronger = &ERROR_LEVELS[strength..];
+        let chosen = match selection {
+            Selection::SmallestVersion | Selection::BoostLevel => (version..=max_version)
+                .find(|version| self.fits(*version, &error_level))
+                .map(|version| (version, error_level)),
+            Selection::FixedVersion => Some((version, error_level)),
+        };
+        let (version, error_level) = match chosen {
+            Some((version, error_level)) if self.fits(version, &error_level) => {
+                (version, error_level)
+            }
+            _ => return Err(Error::DataTooLong),
+        };
+        self.version = version;
+        self.error_level = match selection {
+            Selection::SmallestVersion => error_level,
+            Selection::BoostLevel | Selection::FixedVersion => *stronger
+                .iter()
+                .rev()
+                .find(|level| self.fits(version, level))
+                .unwrap(),
+        };
+        Ok(())
     }
 }
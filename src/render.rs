@@ -0,0 +1,272 @@
+use crate::bitmap::{base64, Bitmap};
+use crate::contour;
+use crate::qr_code::Matrix;
+use std::fmt;
+
+/// Anything drawn as a grid of modules, `0` dark, `1` light and `2` for
+/// modules not placed yet. `modules` holds the symbol alone; the quiet zone
+/// is only added by the renderers. The accessors below read the grid with
+/// `true` for dark, unplaced modules counting as light.
+pub trait Symbol {
+    fn modules(&self) -> &Matrix<u8>;
+    fn width(&self) -> u32 {
+        self.symbol_size().0
+    }
+    fn height(&self) -> u32 {
+        self.symbol_size().1
+    }
+    /// Outside the symbol is light, like the quiet zone.
+    fn is_dark(&self, x: u32, y: u32) -> bool {
+        x < self.width() && y < self.height() && self.modules().get((x, y)) == 0
+    }
+    fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = bool> + '_> + '_
+    where
+        Self: Sized,
+    {
+        (0..self.height()).map(move |y| (0..self.width()).map(move |x| self.is_dark(x, y)))
+    }
+    fn to_bool_grid(&self) -> Vec<Vec<bool>> {
+        (0..self.height())
+            .map(|y| (0..self.width()).map(|x| self.is_dark(x, y)).collect())
+            .collect()
+    }
+    /// Rows of bits, most significant first and `1` for dark, each row
+    /// padded to whole bytes (the PBM `P4` raster layout).
+    fn to_bytes_packed(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity((self.width().div_ceil(8) * self.height()) as usize);
+        for y in 0..self.height() {
+            for start in (0..self.width()).step_by(8) {
+                bytes.push(
+                    (start..(start + 8).min(self.width()))
+                        .filter(|x| self.is_dark(*x, y))
+                        .fold(0, |byte, x| byte | 0x80 >> (x - start)),
+                );
+            }
+        }
+        bytes
+    }
+    /// Light modules the renderers draw around the symbol.
+    fn quiet_zone(&self) -> u32 {
+        QUIET_ZONE
+    }
+    /// Width and height of the symbol in modules.
+    fn symbol_size(&self) -> (u32, u32) {
+        self.modules().size()
+    }
+    /// Width and height in modules once drawn, quiet zone included.
+    fn rendered_size(&self) -> (u32, u32) {
+        let (size_x, size_y) = self.symbol_size();
+        let quiet_zone = self.quiet_zone();
+        (size_x + 2 * quiet_zone, size_y + 2 * quiet_zone)
+    }
+}
+
+/// Coordinates with at most four decimals and no trailing zeros.
+pub(crate) fn number(value: f32) -> String {
+    let text = format!("{:.4}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Default quiet zone in modules, the width ISO/IEC 18004 requires.
+pub const QUIET_ZONE: u32 = 4;
+
+/// Dark modules row by row, surrounded by `quiet_zone` light modules on
+/// every side.
+pub(crate) fn padded_rows(symbol: &dyn Symbol, quiet_zone: u32) -> Vec<Vec<bool>> {
+    let (width, height) = symbol.symbol_size();
+    let mut rows =
+        vec![vec![false; (width + 2 * quiet_zone) as usize]; (height + 2 * quiet_zone) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            rows[(y + quiet_zone) as usize][(x + quiet_zone) as usize] = symbol.is_dark(x, y);
+        }
+    }
+    rows
+}
+
+/// Two module rows per line with `▀`, `▄` and `█`, one column per module.
+/// The glyphs draw dark modules in the terminal's foreground color, for
+/// dark text on a light background; `invert` draws the light modules
+/// instead, for light text on a dark background.
+pub fn to_half_blocks(symbol: &dyn Symbol, invert: bool) -> String {
+    let rows = padded_rows(symbol, symbol.quiet_zone());
+    let mut text = String::new();
+    for pair in rows.chunks(2) {
+        for x in 0..pair[0].len() {
+            let top = pair[0][x] != invert;
+            let bottom = pair.get(1).map_or(invert, |row| row[x]) != invert;
+            text += match (top, bottom) {
+                (true, true) => "█",
+                (true, false) => "▀",
+                (false, true) => "▄",
+                (false, false) => " ",
+            };
+        }
+        text += "\n";
+    }
+    text
+}
+
+/// Two spaces per module on a 24-bit ANSI background color, independent
+/// of the terminal's own colors.
+pub fn to_ansi(symbol: &dyn Symbol) -> String {
+    let mut text = String::new();
+    for row in padded_rows(symbol, symbol.quiet_zone()) {
+        let mut current: Option<bool> = None;
+        for dark in row {
+            if current != Some(dark) {
+                text += if dark {
+                    "\x1b[48;2;0;0;0m"
+                } else {
+                    "\x1b[48;2;255;255;255m"
+                };
+                current = Some(dark);
+            }
+            text += "  ";
+        }
+        text += "\x1b[0m\n";
+    }
+    text
+}
+
+/// Plain ASCII fallback, `##` for a dark module and two spaces for a light one.
+pub fn to_ascii(symbol: &dyn Symbol) -> String {
+    let mut text = String::new();
+    for row in padded_rows(symbol, symbol.quiet_zone()) {
+        for dark in row {
+            text += if dark { "##" } else { "  " };
+        }
+        text += "\n";
+    }
+    text
+}
+
+fn module_chars(value: u8) -> &'static str {
+    match value {
+        1 => "██",
+        0 => "  ",
+        _ => "░░",
+    }
+}
+
+/// Quoted rows of double-width blocks, the output of every symbol's `Debug`.
+pub fn fmt_modules(symbol: &dyn Symbol, f: &mut fmt::Formatter) -> fmt::Result {
+    let modules = symbol.modules();
+    let (size_x, size_y) = modules.size();
+    for i in 0..size_y {
+        let mut row: String = "".to_string();
+        for j in 0..size_x {
+            row += module_chars(modules.get((j, i)));
+        }
+        writeln!(f, "{:?}", row)?;
+    }
+    Ok(())
+}
+
+/// SVG image of `module_size` user units per module, quiet zone included,
+/// the dark regions drawn as one path of traced outlines.
+pub fn to_svg(symbol: &dyn Symbol, module_size: u32) -> String {
+    let (size_x, size_y) = symbol.rendered_size();
+    let (width, height) = (size_x * module_size, size_y * module_size);
+    let mut path: String = "".to_string();
+    for polygon in contour::trace(&padded_rows(symbol, symbol.quiet_zone())) {
+        for (i, (x, y)) in polygon.iter().enumerate() {
+            path += &format!(
+                "{}{} {}",
+                if i == 0 { "M" } else { "L" },
+                x * module_size,
+                y * module_size
+            );
+        }
+        path += "z";
+    }
+    format!(
+        concat!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" ",
+            "viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">",
+            "<rect width=\"{0}\" height=\"{1}\" fill=\"#ffffff\"/>",
+            "<path d=\"{2}\" fill=\"#000000\"/></svg>"
+        ),
+        width, height, path
+    )
+}
+
+/// Sixel image with `scale` pixels per module, two color registers and run
+/// length encoded bands of six pixel rows.
+pub fn to_sixel(symbol: &dyn Symbol, scale: u32) -> String {
+    let bitmap = Bitmap::from_symbol(symbol, scale);
+    let mut sixel = format!(
+        "\x1bPq\"1;1;{};{}#0;2;0;0;0#1;2;100;100;100",
+        bitmap.width, bitmap.height
+    );
+    for band in (0..bitmap.height).step_by(6) {
+        for (register, value) in [(0, 0), (1, 255)] {
+            sixel += &format!("#{}", register);
+            let columns: Vec<u8> = (0..bitmap.width)
+                .map(|x| {
+                    (0..6)
+                        .filter(|bit| {
+                            band + bit < bitmap.height && bitmap.get(x, band + bit) == value
+                        })
+                        .fold(0, |sixel, bit| sixel | 1 << bit)
+                })
+                .collect();
+            let mut x = 0;
+            while x < columns.len() {
+                let run = columns[x..]
+                    .iter()
+                    .take_while(|c| **c == columns[x])
+                    .count();
+                let char = (63 + columns[x]) as char;
+                if run > 3 {
+                    sixel += &format!("!{}{}", run, char);
+                } else {
+                    sixel.extend(std::iter::repeat_n(char, run));
+                }
+                x += run;
+            }
+            sixel += if register == 0 { "$" } else { "-" };
+        }
+    }
+    sixel + "\x1b\\"
+}
+
+/// Kitty graphics protocol escape transmitting and displaying a PNG, the
+/// payload split into the 4096 byte chunks the protocol requires.
+pub fn to_kitty(symbol: &dyn Symbol, scale: u32) -> String {
+    let payload = base64(&Bitmap::from_symbol(symbol, scale).to_png());
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(4096).collect();
+    let mut kitty = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i < chunks.len() - 1) as u8;
+        let control = if i == 0 {
+            format!("a=T,f=100,m={}", more)
+        } else {
+            format!("m={}", more)
+        };
+        kitty += &format!(
+            "\x1b_G{};{}\x1b\\",
+            control,
+            std::str::from_utf8(chunk).unwrap()
+        );
+    }
+    kitty
+}
+
+/// iTerm2 inline image (OSC 1337) of a PNG, shown at its pixel size.
+pub fn to_iterm(symbol: &dyn Symbol, scale: u32) -> String {
+    let bitmap = Bitmap::from_symbol(symbol, scale);
+    let png = bitmap.to_png();
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={}px;height={}px;preserveAspectRatio=1:{}\x07",
+        png.len(),
+        bitmap.width,
+        bitmap.height,
+        base64(&png)
+    )
+}
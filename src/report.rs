@@ -0,0 +1,112 @@
+use crate::capacity::{self, Segment};
+use crate::config::{ErrorLevel, Mask, Model};
+use crate::qr_code::QRcode;
+
+/// Blocks of one group sharing the same number of data codewords.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlockGroup {
+    pub blocks: u16,
+    pub data_codewords: u16,
+    pub error_correction_codewords: u16,
+}
+
+/// How full a symbol is and how its codewords are laid out.
+#[derive(Debug, Clone)]
+pub struct EncodingReport {
+    pub version: u8,
+    pub error_level: ErrorLevel,
+    pub mask: Mask,
+    /// Segments with their bits, mode indicator and length field included.
+    pub segments: Vec<(Segment, u32)>,
+    pub used_bits: u32,
+    pub available_bits: u32,
+    /// Terminator zeros, shortened when the symbol is nearly full.
+    pub terminator_bits: u32,
+    /// `BLANK_FILLER` codewords after the terminator.
+    pub pad_codewords: u32,
+    pub remainder_bits: u32,
+    pub groups: Vec<BlockGroup>,
+    /// Mask penalties N1 to N4 of the rendered symbol.
+    pub penalty: [u32; 4],
+}
+
+impl EncodingReport {
+    pub fn new(qrcode: &QRcode) -> EncodingReport {
+        let rs = &qrcode.rs;
+        let segment = Segment {
+            mode: rs.mode,
+            characters: rs.data().len() as u32,
+        };
+        let segments = vec![(segment, segment.bits(rs.version))];
+        // Model 1 bit streams start with 4 zero bits.
+        let leading_bits = if rs.model == Model::Model1 { 4 } else { 0 };
+        let used_bits = leading_bits + capacity::bits(&[segment], rs.version);
+        let (data_codewords, _, groups, codewords_in_group, error_correction) =
+            rs.error_correction_data();
+        let available_bits = data_codewords as u32 * 8;
+        let terminator_bits = available_bits.saturating_sub(used_bits).min(4);
+        let pad_codewords =
+            available_bits.saturating_sub((used_bits + terminator_bits).div_ceil(8) * 8) / 8;
+        EncodingReport {
+            version: rs.version,
+            error_level: rs.error_level,
+            mask: rs.mask,
+            segments,
+            used_bits,
+            available_bits,
+            terminator_bits,
+            pad_codewords,
+            remainder_bits: rs.remainder_bits() as u32,
+            groups: (0..2)
+                .filter(|group| groups[*group] > 0)
+                .map(|group| BlockGroup {
+                    blocks: groups[group],
+                    data_codewords: codewords_in_group[group] as u16,
+                    error_correction_codewords: error_correction,
+                })
+                .collect(),
+            penalty: qrcode.penalty(),
+        }
+    }
+    pub fn total_penalty(&self) -> u32 {
+        self.penalty.iter().sum()
+    }
+    pub fn to_json(&self) -> String {
+        let segments: Vec<String> = self
+            .segments
+            .iter()
+            .map(|(segment, bits)| {
+                format!(
+                    "{{\"mode\":\"{:?}\",\"characters\":{},\"bits\":{}}}",
+                    segment.mode, segment.characters, bits
+                )
+            })
+            .collect();
+        let groups: Vec<String> = self
+            .groups
+            .iter()
+            .map(|group| {
+                format!(
+                    "{{\"blocks\":{},\"data_codewords\":{},\"error_correction_codewords\":{}}}",
+                    group.blocks, group.data_codewords, group.error_correction_codewords
+                )
+            })
+            .collect();
+        let penalty: Vec<String> = self.penalty.iter().map(|n| n.to_string()).collect();
+        format!(
+            "{{\"version\":{},\"error_level\":\"{:?}\",\"mask\":\"{:03b}\",\"segments\":[{}],\"used_bits\":{},\"available_bits\":{},\"terminator_bits\":{},\"pad_codewords\":{},\"remainder_bits\":{},\"groups\":[{}],\"penalty\":[{}],\"total_penalty\":{}}}",
+            self.version,
+            self.error_level,
+            self.mask as u8,
+            segments.join(","),
+            self.used_bits,
+            self.available_bits,
+            self.terminator_bits,
+            self.pad_codewords,
+            self.remainder_bits,
+            groups.join(","),
+            penalty.join(","),
+            self.total_penalty()
+        )
+    }
+}
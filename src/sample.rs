@@ -0,0 +1,300 @@
+use crate::bch;
+use crate::error::Error;
+use crate::locate::Location;
+use crate::qr_code::Matrix;
+use crate::utils;
+
+/// Projective transform of the plane, `[x', y', w] = matrix * [x, y, 1]`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Perspective {
+    matrix: [[f64; 3]; 3],
+}
+
+impl Perspective {
+    /// Maps the unit square corners `(0, 0)`, `(1, 0)`, `(1, 1)` and `(0, 1)`
+    /// to `corners`.
+    fn square_to_quadrilateral(corners: [(f32, f32); 4]) -> Perspective {
+        let [(x0, y0), (x1, y1), (x2, y2), (x3, y3)] = corners.map(|(x, y)| (x as f64, y as f64));
+        let (dx3, dy3) = (x0 - x1 + x2 - x3, y0 - y1 + y2 - y3);
+        let (g, h) = if dx3 == 0.0 && dy3 == 0.0 {
+            (0.0, 0.0)
+        } else {
+            let (dx1, dx2, dy1, dy2) = (x1 - x2, x3 - x2, y1 - y2, y3 - y2);
+            let denominator = dx1 * dy2 - dx2 * dy1;
+            (
+                (dx3 * dy2 - dx2 * dy3) / denominator,
+                (dx1 * dy3 - dx3 * dy1) / denominator,
+            )
+        };
+        Perspective {
+            matrix: [
+                [x1 - x0 + g * x1, x3 - x0 + h * x3, x0],
+                [y1 - y0 + g * y1, y3 - y0 + h * y3, y0],
+                [g, h, 1.0],
+            ],
+        }
+    }
+    /// Inverse up to scale, which a projective transform ignores.
+    fn adjugate(&self) -> Perspective {
+        let m = &self.matrix;
+        let cofactor = |row: usize, column: usize| {
+            let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+            let (c0, c1) = ((column + 1) % 3, (column + 2) % 3);
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        let mut matrix = [[0.0; 3]; 3];
+        for (row, values) in matrix.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = cofactor(column, row);
+            }
+        }
+        Perspective { matrix }
+    }
+    fn times(&self, other: &Perspective) -> Perspective {
+        let mut matrix = [[0.0; 3]; 3];
+        for (row, values) in matrix.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = (0..3)
+                    .map(|i| self.matrix[row][i] * other.matrix[i][column])
+                    .sum();
+            }
+        }
+        Perspective { matrix }
+    }
+    /// Transform taking each of the four `from` points to the matching `to`
+    /// point.
+    pub fn quadrilateral_to_quadrilateral(
+        from: [(f32, f32); 4],
+        to: [(f32, f32); 4],
+    ) -> Perspective {
+        Perspective::square_to_quadrilateral(to)
+            .times(&Perspective::square_to_quadrilateral(from).adjugate())
+    }
+    pub fn transform(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let m = &self.matrix;
+        let (x, y) = (x as f64, y as f64);
+        let w = m[2][0] * x + m[2][1] * y + m[2][2];
+        (
+            ((m[0][0] * x + m[0][1] * y + m[0][2]) / w) as f32,
+            ((m[1][0] * x + m[1][1] * y + m[1][2]) / w) as f32,
+        )
+    }
+}
+
+/// Image pixel at `(x, y)` is dark; outside the image counts as light.
+fn dark(image: &Matrix<u8>, threshold: u8, (x, y): (f32, f32)) -> bool {
+    let (width, height) = image.size();
+    x >= 0.0
+        && y >= 0.0
+        && (x as u32) < width
+        && (y as u32) < height
+        && image.get((x as u32, y as u32)) < threshold
+}
+
+/// Whether `dimension` is the size of a QR code symbol, versions 1 to 40.
+fn valid_dimension(dimension: u32) -> bool {
+    (21..=177).contains(&dimension) && dimension % 4 == 1
+}
+
+/// Module axes of the symbol in image pixels, from its finder patterns.
+fn axes(location: &Location, dimension: u32) -> ((f32, f32), (f32, f32)) {
+    let span = (dimension - 7) as f32;
+    let (top_left, top_right, bottom_left) =
+        (location.top_left, location.top_right, location.bottom_left);
+    (
+        (
+            (top_right.x - top_left.x) / span,
+            (top_right.y - top_left.y) / span,
+        ),
+        (
+            (bottom_left.x - top_left.x) / span,
+            (bottom_left.y - top_left.y) / span,
+        ),
+    )
+}
+
+/// Center of the bottom right alignment pattern of a symbol `dimension`
+/// modules wide, searched up to 8 modules around where the finder
+/// patterns put it by matching all 25 modules of the pattern along the
+/// symbol axes, at a few scales. `None` for version 1, a `dimension` no
+/// version has or when no position matches at least 23 modules.
+pub fn find_alignment(
+    image: &Matrix<u8>,
+    threshold: u8,
+    location: &Location,
+    dimension: u32,
+) -> Option<(f32, f32)> {
+    if !valid_dimension(dimension) {
+        return None;
+    }
+    let version = ((dimension - 17) / 4) as u8;
+    if version < 2 {
+        return None;
+    }
+    let center = *utils::get_alignment(version).last().unwrap() as f32 + 0.5 - 3.5;
+    let (right, down) = axes(location, dimension);
+    let estimate = (
+        location.top_left.x + right.0 * center + down.0 * center,
+        location.top_left.y + right.1 * center + down.1 * center,
+    );
+    let score = |(x, y): (f32, f32), scale: f32| {
+        let mut score = 0;
+        for row in -2i32..=2 {
+            for column in -2i32..=2 {
+                let (along, across) = (column as f32 * scale, row as f32 * scale);
+                let point = (
+                    x + right.0 * along + down.0 * across,
+                    y + right.1 * along + down.1 * across,
+                );
+                let ring = row.abs().max(column.abs()) == 1;
+                if dark(image, threshold, point) != ring {
+                    score += 1;
+                }
+            }
+        }
+        score
+    };
+    // Perspective makes modules near the bottom right corner larger or
+    // smaller than the finder patterns suggest.
+    let radius = (location.module_size * 8.0).ceil() as i32;
+    let mut best: (u32, Vec<(f32, f32)>) = (0, Vec::new());
+    for scale in [1.0, 0.8, 1.25, 1.5] {
+        let mut found: (u32, Vec<(f32, f32)>) = (0, Vec::new());
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let point = (estimate.0 + dx as f32, estimate.1 + dy as f32);
+                let score = score(point, scale);
+                if score > found.0 {
+                    found = (score, vec![point]);
+                } else if score == found.0 {
+                    found.1.push(point);
+                }
+            }
+        }
+        if found.0 > best.0 {
+            best = found;
+        }
+    }
+    if best.0 < 23 {
+        return None;
+    }
+    // Positions around the center all match; average those within a module
+    // of the one nearest the estimate.
+    let distance =
+        |a: (f32, f32), b: (f32, f32)| ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt();
+    let nearest = *best
+        .1
+        .iter()
+        .min_by(|a, b| distance(**a, estimate).total_cmp(&distance(**b, estimate)))
+        .unwrap();
+    let plateau: Vec<&(f32, f32)> = best
+        .1
+        .iter()
+        .filter(|point| distance(**point, nearest) <= location.module_size)
+        .collect();
+    // A point samples the pixel it falls in, so matches start up to a
+    // pixel before the pattern edges and end on them.
+    let count = plateau.len() as f32;
+    Some((
+        plateau.iter().map(|point| point.0).sum::<f32>() / count + 0.5,
+        plateau.iter().map(|point| point.1).sum::<f32>() / count + 0.5,
+    ))
+}
+
+/// Image to module transform of a symbol `dimension` modules wide: finder
+/// centers and the bottom right alignment center, or for version 1 (or
+/// when it is not found) the point completing the parallelogram.
+pub fn perspective(
+    image: &Matrix<u8>,
+    threshold: u8,
+    location: &Location,
+    dimension: u32,
+) -> Result<Perspective, Error> {
+    if !valid_dimension(dimension) {
+        return Err(Error::InvalidImage("not a QR code symbol size"));
+    }
+    let size = dimension as f32;
+    let (top_left, top_right, bottom_left) =
+        (location.top_left, location.top_right, location.bottom_left);
+    let (corner, image_corner) = match find_alignment(image, threshold, location, dimension) {
+        Some(alignment) => {
+            let version = ((dimension - 17) / 4) as u8;
+            let center = *utils::get_alignment(version).last().unwrap() as f32 + 0.5;
+            ((center, center), alignment)
+        }
+        None => (
+            (size - 3.5, size - 3.5),
+            (
+                top_right.x + bottom_left.x - top_left.x,
+                top_right.y + bottom_left.y - top_left.y,
+            ),
+        ),
+    };
+    Ok(Perspective::quadrilateral_to_quadrilateral(
+        [(3.5, 3.5), (size - 3.5, 3.5), corner, (3.5, size - 3.5)],
+        [
+            (top_left.x, top_left.y),
+            (top_right.x, top_right.y),
+            image_corner,
+            (bottom_left.x, bottom_left.y),
+        ],
+    ))
+}
+
+fn sample_grid(
+    image: &Matrix<u8>,
+    threshold: u8,
+    location: &Location,
+    dimension: u32,
+) -> Result<Matrix<u8>, Error> {
+    let transform = perspective(image, threshold, location, dimension)?;
+    let (width, height) = image.size();
+    let mut modules: Matrix<u8> = Matrix::create(dimension, dimension, &1);
+    for y in 0..dimension {
+        for x in 0..dimension {
+            let point = transform.transform((x as f32 + 0.5, y as f32 + 0.5));
+            if point.0 < 0.0 || point.1 < 0.0 || point.0 >= width as f32 || point.1 >= height as f32
+            {
+                return Err(Error::InvalidImage("symbol extends past the image"));
+            }
+            if dark(image, threshold, point) {
+                modules.put((x, y), 0);
+            }
+        }
+    }
+    Ok(modules)
+}
+
+/// Version from either version information block of a sampled symbol.
+fn read_version(modules: &Matrix<u8>) -> Option<u8> {
+    let (size, _) = modules.size();
+    let mut blocks = [0; 2];
+    for i in (0..18).rev() {
+        let (along, across) = (size - 11 + i % 3, i / 3);
+        for (block, point) in blocks.iter_mut().zip([(along, across), (across, along)]) {
+            *block = *block << 1 | (modules.get(point) == 0) as u32;
+        }
+    }
+    blocks
+        .iter()
+        .filter_map(|block| bch::decode_version(*block))
+        .min_by_key(|(_, errors)| *errors)
+        .map(|(version, _)| version)
+}
+
+/// Samples every module center of a located symbol through the perspective
+/// transform into a grid for `decode::decode`: `0` dark, `1` light, no
+/// quiet zone. From version 7 the version information read back replaces
+/// the estimated version.
+pub fn sample(image: &Matrix<u8>, threshold: u8, location: &Location) -> Result<Matrix<u8>, Error> {
+    let modules = sample_grid(image, threshold, location, location.dimension)?;
+    if location.version < 7 {
+        return Ok(modules);
+    }
+    match read_version(&modules) {
+        Some(version) if version != location.version => {
+            sample_grid(image, threshold, location, version as u32 * 4 + 17)
+        }
+        _ => Ok(modules),
+    }
+}
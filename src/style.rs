@@ -0,0 +1,455 @@
+use crate::bitmap::{Bitmap, RgbaImage};
+use crate::color::{Colors, Paint};
+use crate::config::ModuleKind;
+use crate::error::Error;
+use crate::qr_code::QRcode;
+use crate::render::{number, Symbol};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleShape {
+    Square,
+    Circle,
+    Rounded,
+    Diamond,
+    /// Squares rounded only on the corners no dark neighbour touches, so
+    /// neighbouring modules merge into blobs.
+    Connected,
+}
+
+/// Shape of the finder ring (7x7 minus the 5x5 inside) and of the 3x3 eye.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EyeShape {
+    Square,
+    Rounded,
+    Circle,
+    Diamond,
+}
+
+/// Shapes for data modules and the finder patterns. Timing, alignment,
+/// format and version modules are always drawn as squares so readers keep
+/// finding them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub module: ModuleShape,
+    pub finder_outer: EyeShape,
+    pub finder_inner: EyeShape,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            module: ModuleShape::Square,
+            finder_outer: EyeShape::Square,
+            finder_inner: EyeShape::Square,
+        }
+    }
+}
+
+/// Part of the symbol a shape belongs to, each with its own paint. Data
+/// also covers timing, format and version modules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    Data,
+    FinderOuter,
+    FinderInner,
+    Alignment,
+}
+
+impl Component {
+    const ALL: [Component; 4] = [
+        Component::Data,
+        Component::FinderOuter,
+        Component::FinderInner,
+        Component::Alignment,
+    ];
+    fn paint<'a>(&self, colors: &'a Colors) -> &'a Paint {
+        match self {
+            Component::Data => &colors.data,
+            Component::FinderOuter => &colors.finder_outer,
+            Component::FinderInner => &colors.finder_inner,
+            Component::Alignment => &colors.alignment,
+        }
+    }
+    fn id(&self) -> &'static str {
+        match self {
+            Component::Data => "data",
+            Component::FinderOuter => "finder-outer",
+            Component::FinderInner => "finder-inner",
+            Component::Alignment => "alignment",
+        }
+    }
+}
+
+/// A filled area in module units, origin at the top left of the quiet zone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// Square at `(x, y)` with corner radii top left, top right, bottom
+    /// right and bottom left.
+    Square {
+        x: f32,
+        y: f32,
+        size: f32,
+        radii: [f32; 4],
+    },
+    Diamond {
+        x: f32,
+        y: f32,
+        size: f32,
+    },
+    /// `outer` with `inner` cut out.
+    Ring {
+        outer: Box<Shape>,
+        inner: Box<Shape>,
+    },
+}
+
+impl Shape {
+    fn eye(shape: EyeShape, x: f32, y: f32, size: f32, radius: f32) -> Shape {
+        match shape {
+            EyeShape::Diamond => Shape::Diamond { x, y, size },
+            _ => Shape::Square {
+                x,
+                y,
+                size,
+                radii: [radius; 4],
+            },
+        }
+    }
+    fn eye_radius(shape: EyeShape, size: f32) -> f32 {
+        match shape {
+            EyeShape::Square | EyeShape::Diamond => 0.0,
+            EyeShape::Rounded => size * 0.3,
+            EyeShape::Circle => size / 2.0,
+        }
+    }
+    fn finder_ring(shape: EyeShape, x: f32, y: f32) -> Shape {
+        let radius = Shape::eye_radius(shape, 7.0);
+        Shape::Ring {
+            outer: Box::new(Shape::eye(shape, x, y, 7.0, radius)),
+            inner: Box::new(Shape::eye(
+                shape,
+                x + 1.0,
+                y + 1.0,
+                5.0,
+                (radius - 1.0).max(0.0),
+            )),
+        }
+    }
+    /// Top left corner and side of the square the shape fits in.
+    pub fn bounds(&self) -> (f32, f32, f32) {
+        match self {
+            Shape::Square { x, y, size, .. } | Shape::Diamond { x, y, size } => (*x, *y, *size),
+            Shape::Ring { outer, .. } => outer.bounds(),
+        }
+    }
+    pub fn contains(&self, px: f32, py: f32) -> bool {
+        match self {
+            Shape::Square { x, y, size, radii } => {
+                if px < *x || py < *y || px > x + size || py > y + size {
+                    return false;
+                }
+                let corners = [
+                    (x + radii[0], y + radii[0], radii[0]),
+                    (x + size - radii[1], y + radii[1], radii[1]),
+                    (x + size - radii[2], y + size - radii[2], radii[2]),
+                    (x + radii[3], y + size - radii[3], radii[3]),
+                ];
+                corners.iter().enumerate().all(|(i, (cx, cy, radius))| {
+                    let outside_x = if i == 0 || i == 3 { px < *cx } else { px > *cx };
+                    let outside_y = if i < 2 { py < *cy } else { py > *cy };
+                    !(outside_x && outside_y)
+                        || (px - cx).powi(2) + (py - cy).powi(2) <= radius.powi(2)
+                })
+            }
+            Shape::Diamond { x, y, size } => {
+                (px - x - size / 2.0).abs() + (py - y - size / 2.0).abs() <= size / 2.0
+            }
+            Shape::Ring { outer, inner } => outer.contains(px, py) && !inner.contains(px, py),
+        }
+    }
+    /// SVG path data scaled by `unit`, filled with the even-odd rule.
+    pub fn svg_path(&self, unit: f32) -> String {
+        match self {
+            Shape::Square { x, y, size, radii } => {
+                let (x, y, size) = (x * unit, y * unit, size * unit);
+                let r: Vec<f32> = radii.iter().map(|radius| radius * unit).collect();
+                let arc = |radius: f32, to_x: f32, to_y: f32| {
+                    if radius > 0.0 {
+                        format!(
+                            "A{0} {0} 0 0 1 {1} {2}",
+                            number(radius),
+                            number(to_x),
+                            number(to_y)
+                        )
+                    } else {
+                        String::new()
+                    }
+                };
+                format!(
+                    "M{} {}H{}{}V{}{}H{}{}V{}{}z",
+                    number(x + r[0]),
+                    number(y),
+                    number(x + size - r[1]),
+                    arc(r[1], x + size, y + r[1]),
+                    number(y + size - r[2]),
+                    arc(r[2], x + size - r[2], y + size),
+                    number(x + r[3]),
+                    arc(r[3], x, y + size - r[3]),
+                    number(y + r[0]),
+                    arc(r[0], x + r[0], y)
+                )
+            }
+            Shape::Diamond { x, y, size } => {
+                let (x, y, size) = (x * unit, y * unit, size * unit);
+                format!(
+                    "M{} {}L{} {}L{} {}L{} {}z",
+                    number(x + size / 2.0),
+                    number(y),
+                    number(x + size),
+                    number(y + size / 2.0),
+                    number(x + size / 2.0),
+                    number(y + size),
+                    number(x),
+                    number(y + size / 2.0)
+                )
+            }
+            Shape::Ring { outer, inner } => outer.svg_path(unit) + &inner.svg_path(unit),
+        }
+    }
+}
+
+/// Top left corners of the finder patterns, taken from the modules
+/// `create_finder` marked.
+pub fn finder_positions(qrcode: &QRcode) -> Vec<(u32, u32)> {
+    let kinds = qrcode.module_kinds();
+    let (size_x, size_y) = kinds.size();
+    let finder = |x: u32, y: u32| kinds.get((x, y)) == ModuleKind::Finder;
+    let mut positions = Vec::new();
+    for y in 0..size_y {
+        for x in 0..size_x {
+            if finder(x, y) && (x == 0 || !finder(x - 1, y)) && (y == 0 || !finder(x, y - 1)) {
+                positions.push((x, y));
+            }
+        }
+    }
+    positions
+}
+
+/// Shapes of the whole symbol and its size in modules, quiet zone included.
+pub fn shapes(qrcode: &QRcode, style: &Style) -> (Vec<(Component, Shape)>, u32) {
+    let modules = qrcode.modules();
+    let kinds = qrcode.module_kinds();
+    let size = qrcode.symbol_size().0;
+    let dark = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && x < size as i64
+            && y < size as i64
+            && modules.get((x as u32, y as u32)) == 0
+    };
+    let offset = qrcode.quiet_zone() as f32;
+    let mut shapes = Vec::new();
+    for (finder_x, finder_y) in finder_positions(qrcode) {
+        let (x, y) = (finder_x as f32 + offset, finder_y as f32 + offset);
+        shapes.push((
+            Component::FinderOuter,
+            Shape::finder_ring(style.finder_outer, x, y),
+        ));
+        let radius = Shape::eye_radius(style.finder_inner, 3.0);
+        shapes.push((
+            Component::FinderInner,
+            Shape::eye(style.finder_inner, x + 2.0, y + 2.0, 3.0, radius),
+        ));
+    }
+    for y in 0..size as i64 {
+        for x in 0..size as i64 {
+            if !dark(x, y) {
+                continue;
+            }
+            let kind = kinds.get((x as u32, y as u32));
+            let (left, top) = (x as f32 + offset, y as f32 + offset);
+            let component = if kind == ModuleKind::Alignment {
+                Component::Alignment
+            } else {
+                Component::Data
+            };
+            let styled = matches!(
+                kind,
+                ModuleKind::Data { .. }
+                    | ModuleKind::ErrorCorrection { .. }
+                    | ModuleKind::Remainder
+            );
+            let shape = match (kind, styled, style.module) {
+                (ModuleKind::Finder, _, _) => continue,
+                (_, false, _) | (_, true, ModuleShape::Square) => [0.0; 4],
+                (_, true, ModuleShape::Circle) => [0.5; 4],
+                (_, true, ModuleShape::Rounded) => [0.3; 4],
+                (_, true, ModuleShape::Diamond) => {
+                    shapes.push((
+                        component,
+                        Shape::Diamond {
+                            x: left,
+                            y: top,
+                            size: 1.0,
+                        },
+                    ));
+                    continue;
+                }
+                (_, true, ModuleShape::Connected) => {
+                    let corner = |dx: i64, dy: i64| {
+                        if dark(x + dx, y) || dark(x, y + dy) {
+                            0.0
+                        } else {
+                            0.5
+                        }
+                    };
+                    [corner(-1, -1), corner(1, -1), corner(1, 1), corner(-1, 1)]
+                }
+            };
+            shapes.push((
+                component,
+                Shape::Square {
+                    x: left,
+                    y: top,
+                    size: 1.0,
+                    radii: shape,
+                },
+            ));
+        }
+    }
+    (shapes, qrcode.rendered_size().0)
+}
+
+/// Styled SVG with `module_size` user units per module, one path per
+/// component. Colors failing `Colors::check_contrast` are refused.
+pub fn to_svg(
+    qrcode: &QRcode,
+    style: &Style,
+    colors: &Colors,
+    module_size: u32,
+) -> Result<String, Error> {
+    colors.check_contrast()?;
+    let (shapes, size) = shapes(qrcode, style);
+    let width = size * module_size;
+    let mut definitions = String::new();
+    let mut paths = String::new();
+    for component in Component::ALL {
+        let path: String = shapes
+            .iter()
+            .filter(|(shape_component, _)| *shape_component == component)
+            .map(|(_, shape)| shape.svg_path(module_size as f32))
+            .collect();
+        if path.is_empty() {
+            continue;
+        }
+        let paint = component.paint(colors);
+        if let Some(gradient) = paint.svg_gradient(component.id(), width) {
+            definitions += &gradient;
+        }
+        paths += &format!(
+            "<path d=\"{}\" {} fill-rule=\"evenodd\"/>",
+            path,
+            paint.svg_fill(component.id())
+        );
+    }
+    if !definitions.is_empty() {
+        definitions = format!("<defs>{}</defs>", definitions);
+    }
+    let background = match colors.background {
+        Some(color) => format!(
+            "<rect width=\"{0}\" height=\"{0}\" {1}/>",
+            width,
+            Paint::Solid(color).svg_fill("")
+        ),
+        None => String::new(),
+    };
+    Ok(format!(
+        concat!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" ",
+            "viewBox=\"0 0 {0} {0}\">{1}{2}{3}</svg>"
+        ),
+        width, definitions, background, paths
+    ))
+}
+
+/// Calls `paint` for every pixel whose center falls inside a shape, with
+/// `scale` pixels per module.
+fn rasterize(
+    shapes: &[(Component, Shape)],
+    width: u32,
+    scale: u32,
+    mut paint: impl FnMut(u32, u32, Component),
+) {
+    for (component, shape) in shapes {
+        let (x, y, extent) = shape.bounds();
+        let start_x = (x * scale as f32) as u32;
+        let start_y = (y * scale as f32) as u32;
+        let end = (extent * scale as f32).ceil() as u32;
+        for py in start_y..(start_y + end).min(width) {
+            for px in start_x..(start_x + end).min(width) {
+                let center = (
+                    (px as f32 + 0.5) / scale as f32,
+                    (py as f32 + 0.5) / scale as f32,
+                );
+                if shape.contains(center.0, center.1) {
+                    paint(px, py, *component);
+                }
+            }
+        }
+    }
+}
+
+/// Styled raster with `scale` pixels per module, dark and light only.
+pub fn to_bitmap(qrcode: &QRcode, style: &Style, scale: u32) -> Bitmap {
+    let (shapes, size) = shapes(qrcode, style);
+    let width = size * scale;
+    let mut pixels = vec![255; (width * width) as usize];
+    rasterize(&shapes, width, scale, |x, y, _| {
+        pixels[(y * width + x) as usize] = 0
+    });
+    Bitmap {
+        width,
+        height: width,
+        pixels,
+    }
+}
+
+/// Styled raster in color, for `RgbaImage::to_png`. A `None` background
+/// stays transparent. Colors failing `Colors::check_contrast` are refused.
+pub fn to_rgba(
+    qrcode: &QRcode,
+    style: &Style,
+    colors: &Colors,
+    scale: u32,
+) -> Result<RgbaImage, Error> {
+    colors.check_contrast()?;
+    let (shapes, size) = shapes(qrcode, style);
+    let width = size * scale;
+    let background = colors.background.map_or([0; 4], |color| {
+        [color.red, color.green, color.blue, color.alpha]
+    });
+    let mut pixels = background.repeat((width * width) as usize);
+    rasterize(&shapes, width, scale, |x, y, component| {
+        let position = (
+            (x as f32 + 0.5) / width as f32,
+            (y as f32 + 0.5) / width as f32,
+        );
+        let color = component.paint(colors).at(position.0, position.1);
+        let color = match colors.background {
+            Some(background) if background.alpha == 255 => color.over(&background),
+            _ => color,
+        };
+        let index = ((y * width + x) * 4) as usize;
+        pixels[index..index + 4].copy_from_slice(&[
+            color.red,
+            color.green,
+            color.blue,
+            color.alpha,
+        ]);
+    });
+    Ok(RgbaImage {
+        width,
+        height: width,
+        pixels,
+    })
+}
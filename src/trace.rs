@@ -0,0 +1,193 @@
+use crate::config::ByteNameOfMode;
+use crate::config::ErrorLevel;
+use crate::config::Mask;
+use crate::config::Model;
+use crate::config::MASKS;
+use crate::config::MODE_INDICATOR;
+use crate::polynomial::qr_galois_field;
+use crate::qr_code::QRcode;
+use crate::utils;
+
+pub struct BlockTrace {
+    pub data: Vec<u8>,
+    /// Generator polynomial as exponents of alpha, highest degree first.
+    pub generator: Vec<u32>,
+    pub error_correction: Vec<u8>,
+}
+
+/// Every intermediate result of encoding one symbol, in the order of
+/// ISO/IEC 18004: data analysis, data encoding, error correction,
+/// structure of the final message, masking and format/version information.
+pub struct EncodingTrace {
+    pub mode: ByteNameOfMode,
+    pub version: u8,
+    pub error_level: ErrorLevel,
+    pub mask: Mask,
+    pub mode_indicator: String,
+    pub length_bits: String,
+    pub data_codewords: Vec<u8>,
+    pub blocks: Vec<BlockTrace>,
+    pub interleaved: Vec<u8>,
+    pub remainder_bits: usize,
+    pub format_string: String,
+    pub version_string: Option<String>,
+    pub mask_penalties: Vec<(Mask, [u32; 4])>,
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl EncodingTrace {
+    pub fn new(qrcode: &QRcode) -> EncodingTrace {
+        let rs = &qrcode.rs;
+        let field = qr_galois_field();
+        let length_width = utils::get_len_bit(&rs.mode, rs.version) as usize;
+        let length = format!("{:032b}", rs.data().len());
+        let blocks = rs
+            .blocks()
+            .iter()
+            .map(|(data, error_correction)| BlockTrace {
+                data: data.iter().map(|value| *value as u8).collect(),
+                generator: field
+                    .generator(error_correction.len())
+                    .iter()
+                    .map(|coefficient| field.log(*coefficient))
+                    .collect(),
+                error_correction: error_correction.iter().map(|value| *value as u8).collect(),
+            })
+            .collect();
+        let remainder_bits = rs.remainder_bits();
+        let codeword_bits = &rs.bits[..rs.bits.len() - remainder_bits];
+        let interleaved = (0..codeword_bits.len() / 8)
+            .map(|i| u8::from_str_radix(&codeword_bits[i * 8..i * 8 + 8], 2).unwrap())
+            .collect();
+        let mask_penalties = MASKS
+            .iter()
+            .map(|mask| {
+                let mut masked = QRcode::from_reed_solomon(rs.with_mask(*mask));
+                masked.render();
+                (*mask, masked.penalty())
+            })
+            .collect();
+        EncodingTrace {
+            mode: rs.mode,
+            version: rs.version,
+            error_level: rs.error_level,
+            mask: rs.mask,
+            mode_indicator: MODE_INDICATOR(&rs.mode),
+            length_bits: length[32 - length_width..].to_string(),
+            data_codewords: rs
+                .generate_content()
+                .iter()
+                .map(|value| *value as u8)
+                .collect(),
+            blocks,
+            interleaved,
+            remainder_bits,
+            format_string: rs.create_format_string(),
+            version_string: if rs.model == Model::Model2 && rs.version >= 7 {
+                Some(rs.create_version_string())
+            } else {
+                None
+            },
+            mask_penalties,
+        }
+    }
+    fn summary(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Mode", format!("{:?}", self.mode)),
+            ("Version", self.version.to_string()),
+            ("Error correction level", format!("{:?}", self.error_level)),
+            ("Mask", format!("{:03b}", self.mask as u8)),
+            ("Mode indicator", self.mode_indicator.clone()),
+            ("Character count", self.length_bits.clone()),
+            ("Remainder bits", self.remainder_bits.to_string()),
+            ("Format information", self.format_string.clone()),
+            (
+                "Version information",
+                self.version_string.clone().unwrap_or("-".to_string()),
+            ),
+        ]
+    }
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (name, value) in self.summary() {
+            text += &format!("{:<24}{}\n", name, value);
+        }
+        text += &format!("\nData codewords\n  {}\n", join(&self.data_codewords));
+        for (i, block) in self.blocks.iter().enumerate() {
+            text += &format!("\nBlock {}\n", i + 1);
+            text += &format!("  data       {}\n", join(&block.data));
+            text += &format!("  generator  a^{}\n", block.generator.len() - 1);
+            text += &format!("             {}\n", join(&block.generator));
+            text += &format!("  remainder  {}\n", join(&block.error_correction));
+        }
+        text += &format!("\nInterleaved codewords\n  {}\n", join(&self.interleaved));
+        text += "\nMask penalties (N1 N2 N3 N4 total)\n";
+        for (mask, penalty) in &self.mask_penalties {
+            text += &format!(
+                "  {:03b}  {:>4} {:>4} {:>4} {:>4} {:>5}{}\n",
+                *mask as u8,
+                penalty[0],
+                penalty[1],
+                penalty[2],
+                penalty[3],
+                penalty.iter().sum::<u32>(),
+                if *mask as u8 == self.mask as u8 {
+                    "  <- used"
+                } else {
+                    ""
+                }
+            );
+        }
+        text
+    }
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("## Encoding trace\n\n| Stage | Value |\n|---|---|\n");
+        for (name, value) in self.summary() {
+            markdown += &format!("| {} | `{}` |\n", name, value);
+        }
+        markdown += &format!(
+            "\n### Data codewords\n\n`{}`\n\n### Blocks\n\n",
+            join(&self.data_codewords)
+        );
+        markdown += "| Block | Data codewords | Generator (alpha exponents) | Error correction |\n";
+        markdown += "|---|---|---|---|\n";
+        for (i, block) in self.blocks.iter().enumerate() {
+            markdown += &format!(
+                "| {} | `{}` | `{}` | `{}` |\n",
+                i + 1,
+                join(&block.data),
+                join(&block.generator),
+                join(&block.error_correction)
+            );
+        }
+        markdown += &format!(
+            "\n### Interleaved codewords\n\n`{}`\n\n### Mask penalties\n\n",
+            join(&self.interleaved)
+        );
+        markdown += "| Mask | N1 | N2 | N3 | N4 | Total |\n|---|---|---|---|---|---|\n";
+        for (mask, penalty) in &self.mask_penalties {
+            let name = if *mask as u8 == self.mask as u8 {
+                format!("**{:03b}**", *mask as u8)
+            } else {
+                format!("{:03b}", *mask as u8)
+            };
+            markdown += &format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                name,
+                penalty[0],
+                penalty[1],
+                penalty[2],
+                penalty[3],
+                penalty.iter().sum::<u32>()
+            );
+        }
+        markdown
+    }
+}
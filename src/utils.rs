@@ -1,12 +1,10 @@
+use crate::config;
 use crate::config::ByteNameOfMode;
 use crate::config::ErrorLevel;
 use crate::config::Mask;
 use crate::config::ALIGNMENT_LOCATION_BASE;
 use crate::config::BASE_SIZE;
 use crate::config::LENGTH_BITS;
-use crate::config::VERSION_FORMAT_POLYNOMIAL;
-#[path = "config.rs"]
-mod config;
 
 pub fn get_len_bit(mode: &ByteNameOfMode, version: u8) -> u8 {
     let number_of_mode: u8 = match mode {
@@ -31,20 +29,19 @@ fn get_total_module_data_version(version: u8) -> u16 {
         return 21 * 21 - 3 * 8 * 8 - 2 * 15 - 1 - 2 * 5;
     }
     let alignment_count = ((version as f32 / 7.0).floor() + 2.0) as u16;
-    return ((version * 4 + 17) as i16).pow(2) as u16
-        - 3 * 8 * 8 as u16
+    ((version * 4 + 17) as i16).pow(2) as u16
+        - 3 * 8 * 8_u16
         - (alignment_count.pow(2) - 3) * 25
         - 2 * (version * 4 + 1) as u16
         + (alignment_count - 2) * 10
         - 2 * 15
         - 1
-        - if version > 6 { 2 * 3 * 6 } else { 0 };
+        - if version > 6 { 2 * 3 * 6 } else { 0 }
 }
 
-pub fn get_bin_msg_data(data: &String) -> Vec<String> {
-    let data_into_bytes = data.clone().into_bytes();
+pub fn get_bin_msg_data(data: &[u8]) -> Vec<String> {
     let mut bin_msg_data: Vec<String> = Vec::new();
-    for char in &data_into_bytes {
+    for char in data {
         bin_msg_data.push(format!("{char:08b}"));
     }
     bin_msg_data
@@ -52,19 +49,19 @@ pub fn get_bin_msg_data(data: &String) -> Vec<String> {
 
 pub fn mask(mask: Mask, col: u16, row: u16) -> bool {
     match mask {
-        Mask::_000 => (col + row) % 2 == 0,
-        Mask::_001 => row % 2 == 0,
-        Mask::_010 => col % 3 == 0,
-        Mask::_011 => (col + row) % 3 == 0,
+        Mask::_000 => (col + row).is_multiple_of(2),
+        Mask::_001 => row.is_multiple_of(2),
+        Mask::_010 => col.is_multiple_of(3),
+        Mask::_011 => (col + row).is_multiple_of(3),
         Mask::_100 => ((col as f32 / 3.).floor() + (row as f32 / 2.).floor()) % 2.0 == 0.,
         Mask::_101 => (((row * col) % 2) + ((row * col) % 3)) == 0,
-        Mask::_110 => (((row * col) % 2) + ((row * col) % 3)) % 2 == 0,
-        Mask::_111 => (((row + col) % 2) + ((row * col) % 3)) % 2 == 0,
+        Mask::_110 => (((row * col) % 2) + ((row * col) % 3)).is_multiple_of(2),
+        Mask::_111 => (((row + col) % 2) + ((row * col) % 3)).is_multiple_of(2),
     }
 }
 
 pub fn get_alignment(mut version: u8) -> Vec<u32> {
-    version = version - 2;
+    version -= 2;
     let version_align: u32 = version as u32 * 4;
     let mut align: Vec<u32> = ALIGNMENT_LOCATION_BASE.to_vec();
     let len = align.len();
@@ -77,19 +74,16 @@ pub fn get_alignment(mut version: u8) -> Vec<u32> {
     let mut diff = ((ALIGNMENT_LOCATION_BASE[1] + version_align - align[0]) as f32
         / align_count as f32)
         .ceil() as u32;
-    if diff % 2 != 0 {
+    if !diff.is_multiple_of(2) {
         diff += 1
     }
     for i in 2..7 {
         if align_count >= i {
             let removed = align.len() as u32 - (i - 1);
-            align.insert(
-                removed as usize,
-                align[removed as usize] as u32 - diff as u32,
-            )
+            align.insert(removed as usize, align[removed as usize] - diff)
         }
     }
-    return align;
+    align
 }
 
 pub fn get_codewords_number(version: u8, error_level: &ErrorLevel) -> u32 {
@@ -100,35 +94,14 @@ pub fn get_codewords_number(version: u8, error_level: &ErrorLevel) -> u32 {
         ErrorLevel::H => 3,
     };
     let (error_codewords_per_block, block_number): (u8, u8) =
-        config::TABLE_EC[(version - 1) as usize][index_error_level as usize];
+        config::TABLE_EC[(version - 1) as usize][index_error_level];
     (get_total_module_data_version(version) >> 3) as u32
-        - (error_codewords_per_block as u32 * block_number as u32) as u32
-}
-
-pub fn get_array_bin_polynomial(version_size: usize, i: u8, mut _str: String) -> String {
-    _str += if VERSION_FORMAT_POLYNOMIAL[version_size].contains(&i) {
-        &"1"
-    } else {
-        &"0"
-    };
-    if i <= 0 {
-        return _str;
-    }
-    get_array_bin_polynomial(version_size, i - 1, _str)
-}
-
-pub fn add_padding_without_prefix(string: &String, padding_size: u16) -> String {
-    let padding = ("0".repeat(padding_size as usize)).to_string();
-    return (format!(
-        "{:b}",
-        u32::from_str_radix(&(string.to_owned() + &padding)[0..padding_size as usize], 2).unwrap()
-    ))
-    .to_string();
+        - (error_codewords_per_block as u32 * block_number as u32)
 }
 
 pub fn capacity(bits: u32) -> Vec<u32> {
     vec![
-        bits >> 3 as u32,
+        bits >> 3_u32,
         ((bits / 10 * 3)
             + if bits % 10 > 6 {
                 2
@@ -136,15 +109,24 @@ pub fn capacity(bits: u32) -> Vec<u32> {
                 1
             } else {
                 0
-            }) as u32,
-        ((bits / 11 as u32) * 2 + if bits % 11 > 5 { 1 } else { 0 }) as u32,
-        (bits / 13) as u32,
+            }),
+        ((bits / 11_u32) * 2 + if bits % 11 > 5 { 1 } else { 0 }),
+        (bits / 13),
     ]
 }
 
 pub fn full_capacity(version: u8, error_level: &ErrorLevel, mode: &ByteNameOfMode) -> u32 {
-    let codewords_number = get_codewords_number(version, error_level);
-    let free_modules = (codewords_number << 3) - get_len_bit(&mode, version) as u32;
+    segment_capacity(
+        get_codewords_number(version, error_level) << 3,
+        version,
+        mode,
+    )
+}
+
+/// Characters of a single `mode` segment in `bits`, less its mode
+/// indicator and length field.
+fn segment_capacity(bits: u32, version: u8, mode: &ByteNameOfMode) -> u32 {
+    let free_modules = bits - 4 - get_len_bit(mode, version) as u32;
     let number_of_mode: u8 = match mode {
         ByteNameOfMode::Byte => 0,
         ByteNameOfMode::Numeric => 1,
@@ -154,6 +136,47 @@ pub fn full_capacity(version: u8, error_level: &ErrorLevel, mode: &ByteNameOfMod
     capacity(free_modules)[number_of_mode as usize]
 }
 
+/// `full_capacity` of a QR Model 1 symbol, whose bit stream starts with 4
+/// zero bits.
+pub fn model1_full_capacity(version: u8, error_level: &ErrorLevel, mode: &ByteNameOfMode) -> u32 {
+    let codewords_number = get_model1_error_correction_level_data(version, error_level).0 as u32;
+    segment_capacity((codewords_number << 3) - 4, version, mode)
+}
+
+/// Codewords of a QR Model 1 symbol: 2x4 module blocks on the right edge
+/// and between the left finders, 4x2 blocks in between, less the blocks
+/// taken by extension patterns.
+pub fn get_model1_total_codewords(version: u8) -> u16 {
+    let version = version as u16;
+    2 * (version + 2) + (version + 1) * (2 * version + 8) - 4 + 4 * version - 2 * (version / 2)
+}
+
+/// `get_error_correction_level_data` of a QR Model 1 symbol, where all
+/// blocks hold the same number of data codewords.
+pub fn get_model1_error_correction_level_data(
+    version: u8,
+    error_level: &ErrorLevel,
+) -> (u16, u16, [u16; 2], [f32; 2], u16) {
+    if version > 14 {
+        panic!("Model 1 version is to big")
+    }
+    let index_error_level: usize = match error_level {
+        ErrorLevel::L => 0,
+        ErrorLevel::M => 1,
+        ErrorLevel::Q => 2,
+        ErrorLevel::H => 3,
+    };
+    let (error_codewords_per_block, block_number, codewords_per_block): (u8, u8, u8) =
+        config::TABLE_EC_MODEL1[(version - 1) as usize][index_error_level];
+    (
+        codewords_per_block as u16 * block_number as u16,
+        block_number as u16,
+        [block_number as u16, 0],
+        [codewords_per_block as f32, codewords_per_block as f32 + 1.0],
+        error_codewords_per_block as u16,
+    )
+}
+
 pub fn get_error_correction_level_data(
     version: u8,
     error_level: &ErrorLevel,
@@ -168,8 +191,8 @@ pub fn get_error_correction_level_data(
         ErrorLevel::H => 3,
     };
     let (error_codewords_per_block, block_number): (u8, u8) =
-        config::TABLE_EC[(version - 1) as usize][index_error_level as usize];
-    let total_modules: u16 = (get_total_module_data_version(version) >> 3) as u16;
+        config::TABLE_EC[(version - 1) as usize][index_error_level];
+    let total_modules: u16 = get_total_module_data_version(version) >> 3;
     let second_group = total_modules % block_number as u16;
     let codewords: u16 = total_modules - error_codewords_per_block as u16 * block_number as u16;
     let groups: [u16; 2] = [block_number as u16 - second_group, second_group];